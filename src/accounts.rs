@@ -0,0 +1,207 @@
+use std::{
+    fs, io,
+    path::Path,
+    time::{Duration, Instant},
+};
+
+use serde::{Deserialize, Serialize};
+use snew::{reddit::Reddit, things::Me};
+
+use crate::fetch::LoginCancel;
+
+/// File the accounts are stored in, next to `config.toml`. Only its owner may read it.
+const FILE: &str = "./accounts.toml";
+
+/// A reddit account the user has logged in with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Account {
+    pub name: String,
+    pub refresh_token: String,
+}
+
+/// Every account the user has logged in with. Stored in their own file, as they hold refresh tokens.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Accounts {
+    /// Index of the account in use, `None` when browsing anonymously.
+    pub active: Option<usize>,
+    pub accounts: Vec<Account>,
+    /// The file could not be parsed, so it is left for the user to fix instead of overwritten.
+    #[serde(skip)]
+    pub unreadable: bool,
+}
+
+impl Accounts {
+    /// Key accounts were stored under in eframe's storage, before they got their own file.
+    pub const ID: &'static str = "Accounts";
+
+    /// `None` if there is no accounts file yet.
+    pub fn load() -> Result<Option<Self>, String> {
+        let text = match fs::read_to_string(FILE) {
+            Ok(text) => text,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(format!("Could not read {}: {}", FILE, err)),
+        };
+
+        toml::from_str(&text)
+            .map(Some)
+            .map_err(|err| format!("Could not parse {}: {}", FILE, err))
+    }
+
+    /// Does nothing if the file is [`Self::unreadable`].
+    pub fn store(&self) -> io::Result<()> {
+        if self.unreadable {
+            return Ok(());
+        }
+
+        let text = toml::to_string(self).map_err(io::Error::other)?;
+
+        write_private(Path::new(FILE), text.as_bytes())
+    }
+
+    pub fn active(&self) -> Option<&Account> {
+        self.active.and_then(|idx| self.accounts.get(idx))
+    }
+
+    pub fn position(&self, name: &str) -> Option<usize> {
+        self.accounts
+            .iter()
+            .position(|account| account.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Add an account, or replace the token of one logged in with before. Returns its index.
+    pub fn insert(&mut self, account: Account) -> usize {
+        match self.position(&account.name) {
+            Some(idx) => {
+                self.accounts[idx] = account;
+                idx
+            }
+            None => {
+                self.accounts.push(account);
+                self.accounts.len() - 1
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        if let Some(idx) = self.position(name) {
+            self.accounts.remove(idx);
+
+            self.active = match self.active {
+                Some(active) if active == idx => None,
+                Some(active) if active > idx => Some(active - 1),
+                active => active,
+            };
+        }
+    }
+
+    /// The account after the active one, ending with anonymous browsing before starting over.
+    pub fn next(&self) -> Option<usize> {
+        match self.active {
+            Some(idx) if idx + 1 < self.accounts.len() => Some(idx + 1),
+            Some(_) => None,
+            None if self.accounts.is_empty() => None,
+            None => Some(0),
+        }
+    }
+}
+
+/// A logged in client for an account. Kept while the app runs, so switching back is fast.
+#[derive(Debug)]
+pub struct Session {
+    pub client: Reddit,
+    pub user: Option<Me>,
+}
+
+/// What the accounts window wants done.
+#[derive(Debug)]
+pub enum AccountRequest {
+    /// Switch to the account with this name, or to anonymous browsing.
+    Switch(Option<String>),
+    /// Log in with another account.
+    Add,
+    /// Stop waiting for the login in progress.
+    CancelLogin,
+    Remove(String),
+}
+
+/// How long the user has to authorize SnUI in their browser.
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(240);
+
+#[derive(Debug)]
+pub enum LoginStatus {
+    /// Waiting for the user to authorize SnUI in their browser.
+    Waiting,
+    /// Authorized, finding out who logged in.
+    FetchingUser,
+    Done(String),
+    Failed(String),
+    Cancelled,
+}
+
+/// A login in progress, or the outcome of the last one.
+#[derive(Debug)]
+pub struct Login {
+    /// The authorization url, `None` if the flow could not be started.
+    pub url: Option<String>,
+    pub started: Instant,
+    /// Whether the url was opened in the browser.
+    pub opened: bool,
+    pub status: LoginStatus,
+    /// Stops the listening for the redirect from reddit.
+    cancel: Option<LoginCancel>,
+}
+
+impl Login {
+    pub fn new(started: Result<(String, LoginCancel), String>) -> Self {
+        let (url, cancel, status) = match started {
+            Ok((url, cancel)) => (Some(url), Some(cancel), LoginStatus::Waiting),
+            Err(err) => (None, None, LoginStatus::Failed(err)),
+        };
+
+        Self {
+            url,
+            started: Instant::now(),
+            opened: false,
+            status,
+            cancel,
+        }
+    }
+
+    /// Stop waiting for the user to authorize SnUI, if that is still being waited on.
+    pub fn cancel(&mut self) {
+        if let (LoginStatus::Waiting, Some(cancel)) = (&self.status, &self.cancel) {
+            cancel.cancel();
+            self.status = LoginStatus::Cancelled;
+        }
+    }
+
+    pub fn remaining(&self) -> Duration {
+        LOGIN_TIMEOUT.saturating_sub(self.started.elapsed())
+    }
+}
+
+/// Write a file only its owner can read and write.
+#[cfg(unix)]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    use std::{
+        io::Write,
+        os::unix::fs::{OpenOptionsExt, PermissionsExt},
+    };
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .mode(0o600)
+        .open(path)?;
+
+    // The mode only applies to new files.
+    file.set_permissions(fs::Permissions::from_mode(0o600))?;
+    file.write_all(contents)
+}
+
+/// Other platforms keep files in the user's own directories private by default.
+#[cfg(not(unix))]
+fn write_private(path: &Path, contents: &[u8]) -> io::Result<()> {
+    fs::write(path, contents)
+}
@@ -1,13 +1,30 @@
-use std::sync::Arc;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use eframe::egui::{self, CentralPanel, CtxRef, Response, SidePanel, TopBottomPanel, Window};
 use serde::{Deserialize, Serialize};
 use snew::{
     reddit::Reddit,
-    things::{Me, Post},
+    things::{Me, More, Post, Reply},
+};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    accounts::{AccountRequest, LoginStatus},
+    config::{Key, Options},
+    feed::{FeedSource, SearchSort, Sort, SortKind, SortOrder, TimeRange},
+    image_manager::Image,
+    inbox::{self, InboxItem, InboxKind},
+    profile::{Profile, ProfileTab, UserComment},
+    selftext::SelfText,
+    state::State,
+    tabs::TabRequest,
+    util::{format_age, format_count},
+    Action, Intent, MessageAction, Render,
 };
-
-use crate::{config::Options, state::State, Render};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub enum ComponentMode {
@@ -38,23 +55,21 @@ impl MainContentComponent {
         }
     }
 
-    pub fn render(&self, ctx: &CtxRef, options: &Options, content: &Box<dyn Render>) {
+    pub fn render(&self, ctx: &CtxRef, options: &Options, content: &dyn Render) -> Vec<Intent> {
         match self.mode {
             ComponentMode::Snapped => {
-                CentralPanel::default().show(&ctx, |ui| {
-                    content.render(ui);
-                });
-            }
-            ComponentMode::Floating => {
-                Window::new("Main view")
-                    .title_bar(options.show_title_bars)
-                    .default_width(800f32)
-                    .default_height(600f32)
-                    .show(&ctx, |ui| {
-                        content.render(ui);
-                    });
+                CentralPanel::default()
+                    .show(ctx, |ui| content.render(ui))
+                    .inner
             }
-            ComponentMode::Closed => {}
+            ComponentMode::Floating => Window::new("Main view")
+                .title_bar(options.show_title_bars)
+                .default_width(800f32)
+                .default_height(600f32)
+                .show(ctx, |ui| content.render(ui))
+                .and_then(|response| response.inner)
+                .unwrap_or_default(),
+            ComponentMode::Closed => vec![],
         }
     }
 
@@ -69,13 +84,111 @@ pub type PostId = usize;
 pub struct ViewablePost {
     pub post_id: PostId,
     pub inner: Arc<Post>,
+    /// The original post, if this is a crosspost.
+    pub crosspost: Option<Arc<Post>>,
+    /// The user's vote, which may differ from the one the post was fetched with.
+    pub vote: Vote,
+    pub saved: bool,
+    /// Hidden posts are filtered out of the feed.
+    pub hidden: bool,
+}
+
+/// The user's vote on a post or comment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Vote {
+    Up,
+    Down,
+    None,
+}
+
+impl Vote {
+    /// From reddit's `likes`, which is `None` when the user has not voted.
+    pub fn from_likes(likes: Option<bool>) -> Self {
+        match likes {
+            Some(true) => Self::Up,
+            Some(false) => Self::Down,
+            None => Self::None,
+        }
+    }
+
+    /// How much the vote adds to the score. Also the direction reddit expects.
+    pub fn value(&self) -> i64 {
+        match self {
+            Self::Up => 1,
+            Self::Down => -1,
+            Self::None => 0,
+        }
+    }
+
+    pub fn ui_arrows(&self, ui: &mut egui::Ui) {
+        let color = |vote| {
+            if *self == vote {
+                ui.visuals().hyperlink_color
+            } else {
+                ui.visuals().weak_text_color()
+            }
+        };
+        let (up, down) = (color(Vote::Up), color(Vote::Down));
+
+        ui.colored_label(up, "⬆");
+        ui.colored_label(down, "⬇");
+    }
+}
+
+/// A vote that was applied locally, and should be sent to reddit.
+#[derive(Debug, Clone)]
+pub struct VoteRequest {
+    /// Fullname of the post or comment.
+    pub name: String,
+    pub vote: Vote,
+    /// Vote to go back to if reddit rejects this one.
+    pub previous: Vote,
+}
+
+/// Which list was navigated last, and thus what actions like voting apply to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Focus {
+    Posts,
+    Comments,
+}
+
+impl ViewablePost {
+    pub fn is_image(&self) -> bool {
+        let url = &self.content_post().url;
+
+        url.ends_with(".jpg") || url.ends_with(".png") || url.ends_with(".jpeg")
+    }
+
+    /// Score, including changes to the user's vote since the post was fetched.
+    pub fn score(&self) -> i64 {
+        let fetched_vote = Vote::from_likes(self.inner.likes);
+
+        self.inner.score + self.vote.value() - fetched_vote.value()
+    }
+
+    /// The post whose content should be shown. For crossposts this is the original post.
+    pub fn content_post(&self) -> &Arc<Post> {
+        self.crosspost.as_ref().unwrap_or(&self.inner)
+    }
 }
 
 impl From<(PostId, Post)> for ViewablePost {
     fn from(post: (PostId, Post)) -> Self {
+        let (post_id, mut post) = post;
+
+        let crosspost = post
+            .crosspost_parent_list
+            .take()
+            .and_then(|parents| parents.into_iter().next())
+            .map(Arc::new);
+
         Self {
-            post_id: post.0,
-            inner: Arc::new(post.1),
+            post_id,
+            vote: Vote::from_likes(post.likes),
+            saved: post.saved,
+            hidden: post.hidden,
+            inner: Arc::new(post),
+            crosspost,
         }
     }
 }
@@ -90,6 +203,12 @@ pub struct PostFeedComponent {
     pub viewed: PostId,
     pub mode: ComponentMode,
     just_dragged: bool,
+    /// How far the posts were scrolled last frame.
+    #[serde(skip)]
+    pub scroll_offset: f32,
+    /// Scroll the posts this far next frame, to return to where a feed was left.
+    #[serde(skip)]
+    pub restore_scroll: Option<f32>,
 }
 
 impl PostFeedComponent {
@@ -99,6 +218,8 @@ impl PostFeedComponent {
             viewed: 0,
             mode: ComponentMode::Snapped,
             just_dragged: false,
+            scroll_offset: 0f32,
+            restore_scroll: None,
         }
     }
 
@@ -117,19 +238,25 @@ impl PostFeedComponent {
 }
 
 impl PostFeedComponent {
+    /// Shows the comments of a profile instead of, or between, the posts, depending on its tab.
+    /// Returns what the tab strip wants done.
     pub fn render<'a>(
         &mut self,
         posts: impl Iterator<Item = &'a ViewablePost>,
+        profile: Option<&Profile>,
+        tabs: (&[String], usize),
         ctx: &CtxRef,
         options: &Options,
         auto_scroll: bool,
-    ) {
+    ) -> (Option<TabRequest>, Vec<Intent>) {
+        let mut shown = (None, vec![]);
+
         match self.mode {
             ComponentMode::Snapped => {
                 SidePanel::left("Posts")
                     .default_width(350f32)
                     .show(ctx, |ui| {
-                        self.posts(posts, ui, auto_scroll);
+                        shown = self.contents(posts, profile, tabs, ui, auto_scroll);
                     });
             }
             ComponentMode::Floating => {
@@ -137,54 +264,215 @@ impl PostFeedComponent {
                     .default_width(350f32)
                     .default_height(800f32)
                     .title_bar(options.show_title_bars)
-                    .show(&ctx, |ui| {
-                        self.posts(posts, ui, auto_scroll);
+                    .show(ctx, |ui| {
+                        shown = self.contents(posts, profile, tabs, ui, auto_scroll);
                     });
             }
             ComponentMode::Closed => {}
         }
+
+        shown
+    }
+
+    fn contents<'a>(
+        &mut self,
+        posts: impl Iterator<Item = &'a ViewablePost>,
+        profile: Option<&Profile>,
+        tabs: (&[String], usize),
+        ui: &mut egui::Ui,
+        auto_scroll: bool,
+    ) -> (Option<TabRequest>, Vec<Intent>) {
+        let request = Self::tab_strip(tabs, ui);
+
+        let intents = match profile.map(|profile| (profile.tab, profile)) {
+            Some((ProfileTab::Comments, profile)) => Self::user_comments(profile, ui),
+            Some((ProfileTab::Overview, profile)) => {
+                self.posts(posts, Some(profile), ui, auto_scroll)
+            }
+            _ => self.posts(posts, None, ui, auto_scroll),
+        };
+
+        (request, intents)
+    }
+
+    fn tab_strip((titles, active): (&[String], usize), ui: &mut egui::Ui) -> Option<TabRequest> {
+        let mut request = None;
+
+        ui.horizontal_wrapped(|ui| {
+            for (idx, title) in titles.iter().enumerate() {
+                if ui.selectable_label(idx == active, title).clicked() && idx != active {
+                    request = Some(TabRequest::Switch(idx));
+                }
+
+                if titles.len() > 1 && ui.small_button("✖").on_hover_text("Close tab").clicked() {
+                    request = Some(TabRequest::Close(idx));
+                }
+            }
+
+            if ui.small_button("+").on_hover_text("New tab").clicked() {
+                request = Some(TabRequest::New(FeedSource::Frontpage));
+            }
+        });
+
+        ui.separator();
+
+        request
     }
 
+    /// Shows the comments of `overview` between the posts, newest first. Returns the threads the
+    /// user asked to open.
     fn posts<'a>(
         &mut self,
         posts: impl Iterator<Item = &'a ViewablePost>,
+        overview: Option<&Profile>,
         ui: &mut egui::Ui,
         auto_scroll: bool,
-    ) {
-        egui::ScrollArea::vertical()
+    ) -> Vec<Intent> {
+        let mut intents = vec![];
+        let mut comments = overview
+            .and_then(|profile| profile.comments.as_deref())
+            .unwrap_or_default()
+            .iter()
+            .peekable();
+
+        let mut scroll_area = egui::ScrollArea::vertical()
             .id_source("post_scroller")
-            .auto_shrink([false, false])
-            .show(ui, |ui| {
-                ui.vertical_centered_justified(|ui| {
-                    for (i, post) in posts.enumerate() {
-                        let is_highlighted = self.highlighted == i;
-                        let response = Self::ui_post_summary(ui, &*post.inner, is_highlighted);
+            .auto_shrink([false, false]);
 
-                        if response.clicked() {
-                            self.highlighted = i;
-                        }
+        if let Some(offset) = self.restore_scroll.take() {
+            scroll_area = scroll_area.scroll_offset(offset);
+        }
 
-                        if (is_highlighted || response.clicked()) && auto_scroll {
-                            response.scroll_to_me(egui::Align::Center)
-                        }
+        scroll_area.show_viewport(ui, |ui, viewport| {
+            self.scroll_offset = viewport.min.y;
 
-                        ui.separator();
+            ui.vertical_centered_justified(|ui| {
+                for (i, post) in posts.enumerate() {
+                    while let Some(comment) =
+                        comments.next_if(|comment| comment.created_utc > post.inner.created_utc)
+                    {
+                        intents.extend(Self::ui_user_comment(ui, comment));
                     }
-                });
+
+                    let is_highlighted = self.highlighted == i;
+                    let response = Self::ui_post_summary(ui, post, is_highlighted);
+
+                    if response.clicked() {
+                        self.highlighted = i;
+                    }
+
+                    if (is_highlighted || response.clicked()) && auto_scroll {
+                        response.scroll_to_me(egui::Align::Center)
+                    }
+
+                    ui.separator();
+                }
+
+                // Comments older than every post fetched so far.
+                for comment in comments {
+                    intents.extend(Self::ui_user_comment(ui, comment));
+                }
+
+                if let Some(profile) = overview {
+                    intents.extend(Self::more_comments_button(profile, ui));
+                }
+            });
+        });
+
+        intents
+    }
+
+    /// Returns the threads the user asked to open.
+    fn user_comments(profile: &Profile, ui: &mut egui::Ui) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        let comments = match &profile.comments {
+            Some(comments) => comments,
+            None => {
+                ui.label("Loading comments..");
+                return intents;
+            }
+        };
+
+        if comments.is_empty() {
+            ui.label("No comments.");
+        }
+
+        egui::ScrollArea::vertical()
+            .id_source("profile_comments_scroller")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for comment in comments {
+                    intents.extend(Self::ui_user_comment(ui, comment));
+                }
+
+                intents.extend(Self::more_comments_button(profile, ui));
             });
+
+        intents
+    }
+
+    /// Returns the thread of the comment, if the user asked to open it.
+    fn ui_user_comment(ui: &mut egui::Ui, comment: &UserComment) -> Option<Intent> {
+        let mut intent = None;
+
+        ui.vertical(|ui| {
+            let context = egui::Label::new(format!(
+                "on {} in /r/{}  {} points  {}",
+                comment.link_title,
+                comment.subreddit,
+                comment.score,
+                format_age(comment.created_utc)
+            ))
+            .small()
+            .text_color(ui.visuals().hyperlink_color)
+            .sense(egui::Sense::click());
+
+            if ui.add(context).on_hover_text("Open thread").clicked() {
+                intent = Some(Intent::from_url(&comment.permalink));
+            }
+
+            ui.add(egui::Label::new(&comment.body).wrap(true));
+        });
+        ui.separator();
+
+        intent
     }
 
-    fn ui_post_summary(ui: &mut egui::Ui, post: &Post, highlight: bool) -> Response {
+    /// Loads older comments of the profile, while reddit has any.
+    fn more_comments_button(profile: &Profile, ui: &mut egui::Ui) -> Option<Intent> {
+        if !profile.more_comments || profile.comments.is_none() {
+            return None;
+        }
+
+        if profile.comment_feed.is_none() {
+            ui.label("Loading comments..");
+            return None;
+        }
+
+        ui.button("Load more comments")
+            .clicked()
+            .then_some(Intent::MoreUserComments)
+    }
+
+    fn ui_post_summary(ui: &mut egui::Ui, viewable: &ViewablePost, highlight: bool) -> Response {
+        let post = &viewable.inner;
+
         let response = ui.vertical(|ui| {
             if highlight {
                 ui.visuals_mut().widgets.noninteractive.fg_stroke =
                     egui::Stroke::new(10f32, egui::Color32::WHITE);
             }
 
-            let max_chars = (ui.available_width() / 10f32) as usize;
+            let max_width = ui.available_width();
+            let fonts = ui.fonts();
 
-            let title = PostFeedComponent::create_display_string(&post.title, max_chars);
-            let url = PostFeedComponent::create_display_string(&post.url, max_chars);
+            let title = PostFeedComponent::create_display_string(&post.title, max_width, |c| {
+                fonts.glyph_width(egui::TextStyle::Heading, c)
+            });
+            let url = PostFeedComponent::create_display_string(&post.url, max_width, |c| {
+                fonts.glyph_width(egui::TextStyle::Body, c)
+            });
 
             let title = egui::Label::new(title)
                 .sense(egui::Sense::click())
@@ -198,7 +486,13 @@ impl PostFeedComponent {
                     ui.label(post.num_comments)
                 });
             });
-            ui.label(post.score.to_string() + " points");
+            ui.horizontal(|ui| {
+                viewable.vote.ui_arrows(ui);
+                ui.label(viewable.score().to_string() + " points");
+                if viewable.saved {
+                    ui.label("★").on_hover_text("Saved");
+                }
+            });
 
             response
         });
@@ -206,21 +500,46 @@ impl PostFeedComponent {
         response.inner
     }
 
-    fn create_display_string(original: &String, max_chars: usize) -> String {
-        let mut new: String = original
-            .chars()
-            .filter(|c| c.is_ascii_graphic() || *c == ' ')
-            .take(max_chars as usize)
-            .collect();
+    /// Truncates `original` to fit within `max_width` points, never splitting a grapheme cluster.
+    /// `glyph_width` is the width of a single character in the font the string will be rendered with.
+    fn create_display_string(
+        original: &str,
+        max_width: f32,
+        glyph_width: impl Fn(char) -> f32,
+    ) -> String {
+        let width_of = |s: &str| s.chars().map(&glyph_width).sum::<f32>();
+
+        if width_of(original) <= max_width {
+            return original.to_owned();
+        }
+
+        let ellipsis = "...";
+        let mut remaining = max_width - width_of(ellipsis);
+        let mut new = String::new();
+
+        for grapheme in original.graphemes(true) {
+            remaining -= width_of(grapheme);
+            if remaining < 0f32 {
+                break;
+            }
 
-        if original.len() > max_chars {
-            new.push_str("...");
+            new.push_str(grapheme);
         }
 
+        new.push_str(ellipsis);
+
         new
     }
 }
 
+/// What the summary says about the feed, above the viewed post.
+pub struct FeedHeader<'a> {
+    pub source: &'a FeedSource,
+    /// Name of the sort, if the feed can be sorted.
+    pub sort: Option<String>,
+    pub profile: Option<&'a Profile>,
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct PostSummaryComponent {
     pub mode: ComponentMode,
@@ -236,263 +555,3100 @@ impl PostSummaryComponent {
         &self,
         ctx: &CtxRef,
         options: &Options,
+        header: FeedHeader,
         post: Option<&ViewablePost>,
         user: Option<&Me>,
-    ) {
+        unread: usize,
+    ) -> Vec<Intent> {
         match self.mode {
             ComponentMode::Snapped => {
-                TopBottomPanel::top("top_panel").show(ctx, |ui| {
-                    ui.horizontal(|ui| {
-                        Self::render_summary(post, ui, user);
-                    });
-                });
-            }
-            ComponentMode::Floating => {
-                Window::new("Viewed post")
-                    .title_bar(options.show_title_bars)
-                    .default_width(500f32)
-                    .default_height(100f32)
-                    .resizable(true)
-                    .show(&ctx, |ui| {
-                        Self::render_summary(post, ui, user);
-                    });
+                TopBottomPanel::top("top_panel")
+                    .show(ctx, |ui| {
+                        let mut intents = Self::render_feed(&header, ui);
+                        intents.extend(Self::render_profile(header.profile, ui));
+                        intents.extend(
+                            ui.horizontal(|ui| Self::render_summary(post, ui, user, unread))
+                                .inner,
+                        );
+                        intents
+                    })
+                    .inner
             }
-            ComponentMode::Closed => {}
+            ComponentMode::Floating => Window::new("Viewed post")
+                .title_bar(options.show_title_bars)
+                .default_width(500f32)
+                .default_height(100f32)
+                .resizable(true)
+                .show(ctx, |ui| {
+                    let mut intents = Self::render_feed(&header, ui);
+                    intents.extend(Self::render_profile(header.profile, ui));
+                    intents.extend(Self::render_summary(post, ui, user, unread));
+                    intents
+                })
+                .and_then(|response| response.inner)
+                .unwrap_or_default(),
+            ComponentMode::Closed => vec![],
         }
     }
 
+    /// What the feed shows, and how it is sorted.
+    fn render_feed(header: &FeedHeader, ui: &mut egui::Ui) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        ui.horizontal(|ui| {
+            ui.add(egui::Label::new(header.source.to_string()).strong());
+
+            if let Some(sort) = &header.sort {
+                let sort = egui::Label::new(format!("sorted by {}", sort))
+                    .text_color(ui.visuals().hyperlink_color)
+                    .sense(egui::Sense::click());
+
+                if ui.add(sort).on_hover_text("Change sort").clicked() {
+                    intents.push(Intent::Action(Action::OpenSortWindow));
+                }
+            }
+        });
+
+        intents
+    }
+
+    /// Header of the profile the feed shows, if any.
+    fn render_profile(profile: Option<&Profile>, ui: &mut egui::Ui) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        let profile = match profile {
+            Some(profile) => profile,
+            None => return intents,
+        };
+
+        ui.horizontal(|ui| {
+            ui.heading(format!("/u/{}", profile.name));
+
+            match &profile.about {
+                Some(about) => {
+                    ui.label(format!(
+                        "{} post karma  {} comment karma  account created {}",
+                        about.link_karma,
+                        about.comment_karma,
+                        format_age(about.created_utc)
+                    ));
+                }
+                None => {
+                    ui.label("Loading..");
+                }
+            }
+
+            ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                if ui
+                    .small_button("✖")
+                    .on_hover_text("Close profile")
+                    .clicked()
+                {
+                    intents.push(Intent::Action(Action::Frontpage));
+                }
+            });
+        });
+
+        ui.horizontal(|ui| {
+            for tab in ProfileTab::available(profile.own) {
+                if ui
+                    .selectable_label(profile.tab == *tab, tab.name())
+                    .clicked()
+                {
+                    intents.push(Intent::ProfileTab(*tab));
+                }
+            }
+        });
+
+        intents
+    }
+
     pub fn toggle_mode(&mut self) {
         self.mode = self.mode.next();
     }
 
-    fn render_summary(post: Option<&ViewablePost>, ui: &mut egui::Ui, user: Option<&Me>) {
-        ui.centered_and_justified(|ui| {
+    fn render_summary(
+        post: Option<&ViewablePost>,
+        ui: &mut egui::Ui,
+        user: Option<&Me>,
+        unread: usize,
+    ) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        ui.vertical_centered(|ui| {
+            if user.is_some() && unread > 0 {
+                let badge = egui::Label::new(format!("✉ {} unread", unread))
+                    .text_color(egui::Color32::from_rgb(255, 69, 0))
+                    .sense(egui::Sense::click());
+
+                if ui.add(badge).on_hover_text("Open inbox").clicked() {
+                    intents.push(Intent::Action(Action::OpenInbox));
+                }
+            }
+
             if let Some(post) = post {
-                let post = &post.inner;
                 let user_string = if let Some(user) = user {
                     format!("Logged in as /u/{}", user.name)
                 } else {
                     String::from("")
                 };
 
+                let inner = &post.inner;
                 ui.label(format!(
                     "{} by /u/{}\n{} points\t\t/r/{}\t\t\t\t\t{}",
-                    &post.title, &post.author, &post.score, &post.subreddit, user_string
+                    &inner.title, &inner.author, &inner.score, &inner.subreddit, user_string
                 ));
+
+                let author = egui::Label::new(format!("view /u/{}", &inner.author))
+                    .text_color(ui.visuals().hyperlink_color)
+                    .sense(egui::Sense::click());
+
+                if ui.add(author).on_hover_text("Open profile").clicked() {
+                    intents.push(Intent::OpenUser(inner.author.clone()));
+                }
+
+                if let Some(original) = &post.crosspost {
+                    let link = egui::Label::new(format!(
+                        "crossposted from /r/{} by /u/{}",
+                        &original.subreddit, &original.author
+                    ))
+                    .text_color(ui.visuals().hyperlink_color)
+                    .sense(egui::Sense::click());
+
+                    if ui.add(link).on_hover_text("Open original").clicked() {
+                        intents.push(Intent::Action(Action::OpenCrosspost));
+                    }
+                }
             } else {
                 ui.label("Loading..");
             }
         });
+
+        intents
     }
 }
 
-/// Floatable, potentially open, windows.
-#[derive(Debug)]
-pub struct Windows {
-    windows: Vec<Box<dyn Show>>,
+/// Which post, and whether its crosspost original, comments are shown for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CommentsKey {
+    pub post_id: PostId,
+    pub original: bool,
+    pub sort: CommentSort,
 }
 
-impl Windows {
-    pub fn new() -> Self {
-        Self {
-            windows: vec![
-                Box::new(SubredditWindow::new()),
-                Box::new(FilterWindow::new()),
-            ],
-        }
-    }
+/// Order comments are fetched in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CommentSort {
+    #[default]
+    Best,
+    Top,
+    New,
+    Controversial,
+    Old,
+    QA,
+}
 
-    pub fn open(&mut self, kind: WindowKind) {
-        let window = self
-            .windows
-            .iter_mut()
-            .find(|window| window.kind() == kind)
-            .expect("Uninitialized window");
+impl CommentSort {
+    pub const ALL: [CommentSort; 6] = [
+        Self::Best,
+        Self::Top,
+        Self::New,
+        Self::Controversial,
+        Self::Old,
+        Self::QA,
+    ];
 
-        window.toggle_open();
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|sort| sort == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
     }
 
-    /// Called every frame
-    pub fn update(&mut self, ctx: &CtxRef, reddit: &Reddit, state: &mut State) {
-        for window in self.windows.iter_mut() {
-            window.show(ctx, reddit, state)
+    /// Value of the `sort` parameter reddit expects.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Best => "confidence",
+            Self::Top => "top",
+            Self::New => "new",
+            Self::Controversial => "controversial",
+            Self::Old => "old",
+            Self::QA => "qa",
         }
     }
-}
-
-#[derive(Debug, Eq, PartialEq)]
-pub enum WindowKind {
-    Subreddit,
-    Filter,
-}
-
-#[derive(Debug, Serialize, Deserialize)]
-struct WindowState {
-    open: bool,
-    request_focus: bool,
-}
 
-impl WindowState {
-    fn new() -> Self {
-        Self {
-            open: false,
-            request_focus: true,
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Best => "Best",
+            Self::Top => "Top",
+            Self::New => "New",
+            Self::Controversial => "Controversial",
+            Self::Old => "Old",
+            Self::QA => "Q&A",
         }
     }
 }
 
-pub trait Show: std::fmt::Debug {
-    fn show(&mut self, ctx: &egui::CtxRef, reddit: &Reddit, state: &mut State);
-    fn kind(&self) -> WindowKind;
-    fn toggle_open(&mut self);
+/// Ways the highlighted comment can move.
+#[derive(Debug, Clone, Copy)]
+pub enum CommentMove {
+    Next,
+    Previous,
+    NextSibling,
+    PreviousSibling,
+    Parent,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct FilterWindow {
-    window: WindowState,
-    checked: bool,
+/// Comments of a post, flattened in the order they are displayed.
+#[derive(Debug)]
+pub struct CommentTree {
+    pub key: CommentsKey,
+    /// `None` while loading.
+    pub comments: Option<Vec<ViewableComment>>,
+    /// Why the comments could not be loaded.
+    pub error: Option<String>,
+    /// Index of the highlighted comment
+    pub highlighted: usize,
+    /// Names of the comments whose replies are hidden.
+    collapsed: HashSet<String>,
+    /// Parents of the "load more" stubs currently being fetched. Stubs are told apart by their parent,
+    /// as every "continue this thread" stub has the same name.
+    loading_more: HashSet<String>,
 }
 
-impl FilterWindow {
-    fn new() -> Self {
+impl CommentTree {
+    pub fn loading(key: CommentsKey) -> Self {
         Self {
-            window: WindowState::new(),
-            checked: false,
+            key,
+            comments: None,
+            error: None,
+            highlighted: 0,
+            collapsed: HashSet::new(),
+            loading_more: HashSet::new(),
         }
     }
-}
 
-impl Show for FilterWindow {
-    fn show(&mut self, ctx: &egui::CtxRef, reddit: &Reddit, state: &mut State) {
-        let mut should_close = false;
+    pub fn is_collapsed(&self, idx: usize) -> bool {
+        self.comments
+            .as_ref()
+            .and_then(|comments| comments.get(idx))
+            .is_some_and(|comment| self.collapsed.contains(&comment.name))
+    }
 
-        if !self.window.open {
-            self.window.request_focus = true;
-        }
+    /// Indices of the comments that are not hidden by a collapsed ancestor.
+    pub fn visible(&self) -> Vec<usize> {
+        let comments = match &self.comments {
+            Some(comments) => comments,
+            None => return vec![],
+        };
 
-        egui::Window::new("Filters")
-            .open(&mut self.window.open)
-            .title_bar(state.options.show_title_bars)
-            .show(ctx, |ui| {
-                ui.add_space(10f32);
-                let response = ui
-                    .horizontal(|ui| {
-                        ui.label("Posts: ");
-                        ui.checkbox(&mut self.checked, "Only renderable")
-                    })
-                    .response;
+        let mut visible = vec![];
+        let mut hidden_below = None;
 
-                ui.add_space(10f32);
+        for (i, comment) in comments.iter().enumerate() {
+            if let Some(depth) = hidden_below {
+                if comment.depth > depth {
+                    continue;
+                }
+                hidden_below = None;
+            }
+
+            visible.push(i);
+
+            if self.collapsed.contains(&comment.name) {
+                hidden_below = Some(comment.depth);
+            }
+        }
+
+        visible
+    }
+
+    pub fn move_cursor(&mut self, movement: CommentMove) {
+        let comments = match &self.comments {
+            Some(comments) => comments,
+            None => return,
+        };
+
+        let current = self.highlighted;
+        let depth = match comments.get(current) {
+            Some(comment) => comment.depth,
+            None => return,
+        };
+
+        let target = match movement {
+            CommentMove::Next => self.visible().into_iter().find(|&i| i > current),
+            CommentMove::Previous => self.visible().into_iter().rev().find(|&i| i < current),
+            // Siblings are the closest comments on the same depth, without leaving the parent.
+            CommentMove::NextSibling => comments[current + 1..]
+                .iter()
+                .position(|comment| comment.depth <= depth)
+                .map(|offset| current + 1 + offset)
+                .filter(|&i| comments[i].depth == depth),
+            CommentMove::PreviousSibling => comments[..current]
+                .iter()
+                .rposition(|comment| comment.depth <= depth)
+                .filter(|&i| comments[i].depth == depth),
+            CommentMove::Parent => comments[current].parent,
+        };
+
+        if let Some(target) = target {
+            self.highlighted = target;
+        }
+    }
+
+    /// Collapse or expand the replies to the highlighted comment.
+    pub fn toggle_collapse(&mut self) {
+        let name = match self
+            .comments
+            .as_ref()
+            .and_then(|comments| comments.get(self.highlighted))
+        {
+            Some(comment) => comment.name.clone(),
+            None => return,
+        };
+
+        if !self.collapsed.remove(&name) {
+            self.collapsed.insert(name);
+        }
+    }
+
+    /// Collapse every top level comment, or expand them if they all are collapsed.
+    pub fn toggle_collapse_top_level(&mut self) {
+        let comments = match &self.comments {
+            Some(comments) => comments,
+            None => return,
+        };
+
+        let top_level: Vec<&String> = comments
+            .iter()
+            .filter(|comment| comment.depth == 0)
+            .map(|comment| &comment.name)
+            .collect();
+
+        if top_level.iter().all(|name| self.collapsed.contains(*name)) {
+            for name in top_level {
+                self.collapsed.remove(name);
+            }
+        } else {
+            self.collapsed.extend(top_level.into_iter().cloned());
+
+            // Keep the cursor visible.
+            while let Some(parent) = comments.get(self.highlighted).and_then(|c| c.parent) {
+                self.highlighted = parent;
+            }
+        }
+    }
+
+    pub fn set_comments(&mut self, replies: Vec<Reply>) {
+        let mut flattened = vec![];
+        for reply in replies {
+            ViewableComment::flatten(reply, 0, None, &mut flattened, 0);
+        }
+
+        self.comments = Some(flattened);
+    }
+
+    /// The highlighted entry, if it is a "load more" stub that is not already loading.
+    pub fn highlighted_stub(&self) -> Option<&More> {
+        self.comments
+            .as_ref()
+            .and_then(|comments| comments.get(self.highlighted))
+            .filter(|comment| !self.is_loading_more(comment))
+            .and_then(|comment| comment.more.as_ref())
+    }
+
+    /// Marks the stub below `parent_id` as loading. Returns the stub, unless it is already loading.
+    pub fn start_loading_more(&mut self, parent_id: &str) -> Option<More> {
+        if self.loading_more.contains(parent_id) {
+            return None;
+        }
+
+        let more = self
+            .comments
+            .as_ref()?
+            .iter()
+            .filter_map(|comment| comment.more.as_ref())
+            .find(|more| more.parent_id == parent_id)?
+            .clone();
+
+        self.loading_more.insert(parent_id.to_owned());
+
+        Some(more)
+    }
+
+    /// Whether `comment` is a "load more" stub that is being fetched.
+    pub fn is_loading_more(&self, comment: &ViewableComment) -> bool {
+        comment
+            .more
+            .as_ref()
+            .is_some_and(|more| self.loading_more.contains(&more.parent_id))
+    }
+
+    pub fn failed_loading_more(&mut self, parent_id: &str) {
+        self.loading_more.remove(parent_id);
+    }
+
+    /// Change the vote on the comment named `name`, adjusting its score. Returns the previous vote.
+    pub fn set_vote(&mut self, name: &str, vote: Vote) -> Option<Vote> {
+        let comment = self
+            .comments
+            .as_mut()?
+            .iter_mut()
+            .find(|comment| comment.more.is_none() && comment.name == name)?;
+
+        let previous = comment.vote;
+        comment.score += vote.value() - previous.value();
+        comment.vote = vote;
+
+        Some(previous)
+    }
+
+    /// Insert a new reply as the first child of the comment or post named `parent`, and highlight it.
+    pub fn insert_reply(&mut self, parent: &str, reply: Reply) {
+        let comments = match self.comments.as_mut() {
+            Some(comments) => comments,
+            None => return,
+        };
+
+        let parent = comments.iter().position(|comment| comment.name == parent);
+        let (idx, depth) = match parent {
+            Some(parent) => (parent + 1, comments[parent].depth + 1),
+            // Replies to the post itself go on top.
+            None => (0, 0),
+        };
+
+        let mut inserted = vec![];
+        ViewableComment::flatten(reply, depth, parent, &mut inserted, idx);
+
+        let len = inserted.len();
+        for comment in comments[idx..].iter_mut() {
+            comment.parent = comment.parent.map(|i| if i >= idx { i + len } else { i });
+        }
+
+        comments.splice(idx..idx, inserted);
+
+        self.highlighted = idx;
+    }
+
+    /// Replace the "load more" stub below `parent_id` with the comments it stood for, keeping the cursor on
+    /// the same comment.
+    pub fn expand_more(&mut self, parent_id: &str, replies: Vec<Reply>) {
+        self.loading_more.remove(parent_id);
+
+        let comments = match self.comments.as_mut() {
+            Some(comments) => comments,
+            None => return,
+        };
+
+        let idx = match comments.iter().position(|comment| {
+            comment
+                .more
+                .as_ref()
+                .is_some_and(|more| more.parent_id == parent_id)
+        }) {
+            Some(idx) => idx,
+            None => return,
+        };
+
+        let (depth, parent) = (comments[idx].depth, comments[idx].parent);
+
+        let mut expanded = vec![];
+        for reply in replies {
+            ViewableComment::flatten(reply, depth, parent, &mut expanded, idx);
+        }
+
+        // Everything after the stub moves by the number of new entries, minus the stub itself.
+        let len = expanded.len();
+        let shift = |i: usize| if i > idx { i + len - 1 } else { i };
+
+        for comment in comments[idx + 1..].iter_mut() {
+            comment.parent = comment.parent.map(shift);
+        }
+
+        comments.splice(idx..=idx, expanded);
+
+        self.highlighted = shift(self.highlighted).min(comments.len().saturating_sub(1));
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ViewableComment {
+    /// Fullname, e.g. t1_abc123
+    pub name: String,
+    pub author: String,
+    pub body: String,
+    pub score: i64,
+    pub created_utc: f64,
+    /// Number of ancestors.
+    pub depth: usize,
+    /// Index of the parent comment in the flattened tree.
+    pub parent: Option<usize>,
+    /// Set if this is not a comment, but a stub for more comments reddit left out.
+    pub more: Option<More>,
+    pub vote: Vote,
+}
+
+impl ViewableComment {
+    /// Flatten `reply` and its replies into `out`. `offset` is the index `out` starts at in the tree.
+    fn flatten(
+        reply: Reply,
+        depth: usize,
+        parent: Option<usize>,
+        out: &mut Vec<Self>,
+        offset: usize,
+    ) {
+        let idx = offset + out.len();
+
+        match reply {
+            Reply::Comment(comment) => {
+                out.push(Self {
+                    name: comment.name,
+                    author: comment.author,
+                    body: comment.body,
+                    score: comment.score,
+                    created_utc: comment.created_utc,
+                    depth,
+                    parent,
+                    more: None,
+                    vote: Vote::from_likes(comment.likes),
+                });
+
+                for reply in comment.replies {
+                    Self::flatten(reply, depth + 1, Some(idx), out, offset);
+                }
+            }
+            Reply::More(more) => out.push(Self {
+                name: more.name.clone(),
+                author: String::new(),
+                body: String::new(),
+                score: 0,
+                created_utc: 0f64,
+                depth,
+                parent,
+                more: Some(more),
+                vote: Vote::None,
+            }),
+        }
+    }
+
+    /// Reddit uses a stub without any children for "continue this thread".
+    fn stub_text(more: &More) -> String {
+        if more.count == 0 {
+            String::from("continue this thread →")
+        } else {
+            format!("load more comments ({})", more.count)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CommentsComponent {
+    pub mode: ComponentMode,
+    /// Show comments of the original post instead, if the post with this id is a crosspost.
+    #[serde(skip)]
+    pub original_of: Option<PostId>,
+    /// The highlighted comment moved, and should be scrolled to.
+    #[serde(skip)]
+    pub scroll_to_highlighted: bool,
+    /// Order comments are fetched in.
+    #[serde(default)]
+    pub sort: CommentSort,
+}
+
+impl CommentsComponent {
+    const INDENT: f32 = 12f32;
+
+    pub fn new() -> Self {
+        Self {
+            mode: ComponentMode::Snapped,
+            original_of: None,
+            scroll_to_highlighted: false,
+            sort: CommentSort::Best,
+        }
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = self.mode.next();
+    }
+
+    pub fn is_open(&self) -> bool {
+        !matches!(self.mode, ComponentMode::Closed)
+    }
+
+    pub fn render(
+        &mut self,
+        ctx: &CtxRef,
+        options: &Options,
+        tree: Option<&mut CommentTree>,
+    ) -> Vec<Intent> {
+        let auto_scroll = std::mem::take(&mut self.scroll_to_highlighted);
+        let sort = &mut self.sort;
+
+        match self.mode {
+            ComponentMode::Snapped => {
+                SidePanel::right("Comments")
+                    .default_width(400f32)
+                    .show(ctx, |ui| Self::comments(tree, sort, ui, auto_scroll))
+                    .inner
+            }
+            ComponentMode::Floating => Window::new("Comments")
+                .default_width(400f32)
+                .default_height(800f32)
+                .title_bar(options.show_title_bars)
+                .show(ctx, |ui| Self::comments(tree, sort, ui, auto_scroll))
+                .and_then(|response| response.inner)
+                .unwrap_or_default(),
+            ComponentMode::Closed => vec![],
+        }
+    }
+
+    fn comments(
+        tree: Option<&mut CommentTree>,
+        sort: &mut CommentSort,
+        ui: &mut egui::Ui,
+        auto_scroll: bool,
+    ) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        egui::ComboBox::from_id_source("comment_sort")
+            .selected_text(format!("Sort by: {}", sort.name()))
+            .show_ui(ui, |ui| {
+                for option in CommentSort::ALL {
+                    ui.selectable_value(sort, option, option.name());
+                }
+            });
+
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_source("comment_scroller")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                let tree = match tree {
+                    Some(tree) if tree.comments.is_some() => tree,
+                    Some(CommentTree {
+                        error: Some(err), ..
+                    }) => {
+                        let text = format!("Could not load comments: {}", err);
+                        ui.colored_label(egui::Color32::RED, text);
+                        return;
+                    }
+                    _ => {
+                        ui.label("Loading..");
+                        return;
+                    }
+                };
+
+                let visible = tree.visible();
+                if visible.is_empty() {
+                    ui.label("No comments");
+                    return;
+                }
+
+                let mut clicked = None;
+
+                if let Some(comments) = &tree.comments {
+                    for i in visible {
+                        let is_highlighted = tree.highlighted == i;
+                        let response = Self::ui_comment(
+                            ui,
+                            &comments[i],
+                            is_highlighted,
+                            tree.is_collapsed(i),
+                            tree.is_loading_more(&comments[i]),
+                            &mut intents,
+                        );
+
+                        if response.clicked() {
+                            clicked = Some(i);
+
+                            if let Some(more) = &comments[i].more {
+                                intents.push(Intent::LoadMoreComments(more.parent_id.clone()));
+                            }
+                        }
+
+                        if (is_highlighted || response.clicked()) && auto_scroll {
+                            response.scroll_to_me(egui::Align::Center)
+                        }
+                    }
+                }
+
+                if let Some(i) = clicked {
+                    tree.highlighted = i;
+                }
+            });
+
+        intents
+    }
+
+    fn ui_comment(
+        ui: &mut egui::Ui,
+        comment: &ViewableComment,
+        highlight: bool,
+        collapsed: bool,
+        loading: bool,
+        intents: &mut Vec<Intent>,
+    ) -> Response {
+        let indent = Self::INDENT * comment.depth as f32;
+
+        let response = ui
+            .horizontal(|ui| {
+                if highlight {
+                    ui.visuals_mut().widgets.noninteractive.fg_stroke =
+                        egui::Stroke::new(10f32, egui::Color32::WHITE);
+                }
+
+                ui.add_space(indent);
+
+                if let Some(more) = &comment.more {
+                    let text = if loading {
+                        String::from("Loading..")
+                    } else {
+                        ViewableComment::stub_text(more)
+                    };
+
+                    ui.add(egui::Label::new(text).text_color(ui.visuals().hyperlink_color));
+                    return;
+                }
+
+                ui.vertical(|ui| {
+                    ui.horizontal(|ui| {
+                        comment.vote.ui_arrows(ui);
+                        if collapsed {
+                            ui.add(egui::Label::new("[+]").small());
+                        }
+
+                        let author = egui::Label::new(format!("/u/{}", comment.author))
+                            .small()
+                            .text_color(ui.visuals().hyperlink_color)
+                            .sense(egui::Sense::click());
+                        if ui.add(author).clicked() {
+                            intents.push(Intent::OpenUser(comment.author.clone()));
+                        }
+
+                        ui.add(
+                            egui::Label::new(format!(
+                                "{} points  {}",
+                                comment.score,
+                                format_age(comment.created_utc)
+                            ))
+                            .small(),
+                        );
+                    });
+
+                    if !collapsed {
+                        ui.add(egui::Label::new(&comment.body).wrap(true));
+                    }
+                });
+            })
+            .response
+            .interact(egui::Sense::click());
+
+        // Indentation guides, one for each ancestor.
+        let rect = response.rect;
+        let stroke = ui.visuals().widgets.noninteractive.bg_stroke;
+        for level in 0..comment.depth {
+            let x = rect.left() + Self::INDENT * level as f32 + Self::INDENT / 2f32;
+            ui.painter().line_segment(
+                [egui::pos2(x, rect.top()), egui::pos2(x, rect.bottom())],
+                stroke,
+            );
+        }
+
+        ui.add_space(6f32);
+
+        response
+    }
+}
+
+/// A subreddit the user is subscribed to.
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub name: String,
+    pub icon_url: Option<String>,
+}
+
+/// A subreddit found by searching.
+#[derive(Debug, Clone)]
+pub struct SubredditInfo {
+    pub name: String,
+    /// `None` if the subreddit hides it.
+    pub subscribers: Option<u64>,
+    pub description: String,
+    pub nsfw: bool,
+}
+
+/// A search for subreddits, and what it found.
+#[derive(Debug)]
+pub struct SubredditSearch {
+    pub query: String,
+    /// `None` while searching.
+    pub results: Option<Result<Vec<SubredditInfo>, String>>,
+}
+
+/// A subreddit being opened from the subreddit window, and whether it could be.
+#[derive(Debug)]
+pub struct SubredditCheck {
+    pub name: String,
+    /// `None` while fetching its first posts.
+    pub result: Option<Result<(), String>>,
+}
+
+/// The subreddits the user is subscribed to. Logged in users get their reddit subscriptions,
+/// anonymous users a list stored with the app.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SubscriptionsComponent {
+    pub mode: ComponentMode,
+    /// Subscriptions of anonymous users.
+    #[serde(default)]
+    pub local: Vec<String>,
+    /// Subreddits pinned to the top, by lowercase name.
+    #[serde(default)]
+    pub favourites: HashSet<String>,
+    /// Subscriptions of the logged in user, `None` until fetched.
+    #[serde(skip)]
+    pub remote: Option<Vec<Subscription>>,
+    /// Whether `remote` has been requested.
+    #[serde(skip)]
+    pub requested: bool,
+    /// Subreddit icons, by lowercase name.
+    #[serde(skip)]
+    pub icons: HashMap<String, Image>,
+    /// Name typed into the subscribe field.
+    #[serde(skip)]
+    text: String,
+}
+
+impl SubscriptionsComponent {
+    const ICON_SIZE: f32 = 16f32;
+
+    pub fn new() -> Self {
+        Self {
+            mode: ComponentMode::Snapped,
+            local: vec![],
+            favourites: HashSet::new(),
+            remote: None,
+            requested: false,
+            icons: HashMap::new(),
+            text: String::new(),
+        }
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = self.mode.next();
+    }
+
+    pub fn is_open(&self) -> bool {
+        !matches!(self.mode, ComponentMode::Closed)
+    }
+
+    /// Forget the subscriptions of the logged in user, so they are fetched again.
+    pub fn reset_remote(&mut self) {
+        self.remote = None;
+        self.requested = false;
+    }
+
+    /// Subscribe or unsubscribe. Reddit is not told about it, only the shown list is updated.
+    pub fn set_subscribed(&mut self, name: &str, subscribed: bool, logged_in: bool) {
+        if logged_in {
+            if let Some(remote) = &mut self.remote {
+                remote.retain(|sub| !sub.name.eq_ignore_ascii_case(name));
+                if subscribed {
+                    remote.push(Subscription {
+                        name: name.to_owned(),
+                        icon_url: None,
+                    });
+                }
+            }
+        } else {
+            self.local.retain(|sub| !sub.eq_ignore_ascii_case(name));
+            if subscribed {
+                self.local.push(name.to_owned());
+            }
+        }
+    }
+
+    /// Every subreddit the user is subscribed to, logged in or not.
+    pub fn known(&self) -> impl Iterator<Item = &str> {
+        self.remote
+            .iter()
+            .flatten()
+            .map(|sub| sub.name.as_str())
+            .chain(self.local.iter().map(String::as_str))
+    }
+
+    /// Names of the shown subreddits, favourites first, then alphabetically.
+    fn entries(&self, logged_in: bool) -> Vec<String> {
+        let mut names: Vec<String> = if logged_in {
+            self.remote
+                .iter()
+                .flatten()
+                .map(|sub| sub.name.clone())
+                .collect()
+        } else {
+            self.local.clone()
+        };
+
+        names.sort_by_key(|name| {
+            let name = name.to_lowercase();
+            (!self.favourites.contains(&name), name)
+        });
+
+        names
+    }
+
+    pub fn render(&mut self, ctx: &CtxRef, options: &Options, logged_in: bool) -> Vec<Intent> {
+        match self.mode {
+            ComponentMode::Snapped => {
+                SidePanel::left("Subscriptions")
+                    .default_width(180f32)
+                    .show(ctx, |ui| self.subscriptions(ui, logged_in))
+                    .inner
+            }
+            ComponentMode::Floating => Window::new("Subscriptions")
+                .default_width(180f32)
+                .default_height(600f32)
+                .title_bar(options.show_title_bars)
+                .show(ctx, |ui| self.subscriptions(ui, logged_in))
+                .and_then(|response| response.inner)
+                .unwrap_or_default(),
+            ComponentMode::Closed => vec![],
+        }
+    }
+
+    fn subscriptions(&mut self, ui: &mut egui::Ui, logged_in: bool) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(&mut self.text)
+                    .hint_text("Subreddit")
+                    .desired_width(110f32),
+            );
+
+            let entered = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+            let name = self.text.trim().trim_start_matches("r/").to_owned();
+
+            if (ui.button("Subscribe").clicked() || entered) && !name.is_empty() {
+                intents.push(Intent::SetSubscribed {
+                    subreddit: name,
+                    subscribed: true,
+                });
+                self.text.clear();
+            }
+        });
+
+        ui.separator();
+
+        if logged_in && self.remote.is_none() {
+            ui.label("Loading..");
+            return intents;
+        }
+
+        egui::ScrollArea::vertical()
+            .id_source("subscriptions_scroller")
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for name in self.entries(logged_in) {
+                    let key = name.to_lowercase();
+                    let favourite = self.favourites.contains(&key);
+
+                    ui.horizontal(|ui| {
+                        match self.icons.get(&key) {
+                            Some(icon) => {
+                                ui.image(icon.id, egui::vec2(Self::ICON_SIZE, Self::ICON_SIZE));
+                            }
+                            None => ui.add_space(Self::ICON_SIZE + ui.spacing().item_spacing.x),
+                        }
+
+                        if ui
+                            .add(egui::Label::new(&name).sense(egui::Sense::click()))
+                            .on_hover_cursor(egui::CursorIcon::PointingHand)
+                            .clicked()
+                        {
+                            intents.push(Intent::OpenSubreddit(name.clone()));
+                        }
+
+                        ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                            if ui.small_button("✖").on_hover_text("Unsubscribe").clicked() {
+                                intents.push(Intent::SetSubscribed {
+                                    subreddit: name.clone(),
+                                    subscribed: false,
+                                });
+                            }
+
+                            let star = if favourite { "★" } else { "☆" };
+                            if ui.small_button(star).on_hover_text("Favourite").clicked() {
+                                if favourite {
+                                    self.favourites.remove(&key);
+                                } else {
+                                    self.favourites.insert(key.clone());
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+
+        intents
+    }
+}
+
+/// Something a reply can be written to.
+#[derive(Debug, Clone)]
+pub struct ReplyTarget {
+    /// Fullname of the post or comment.
+    pub name: String,
+    /// Description shown in the composer.
+    pub title: String,
+    /// Comments the reply should be inserted into.
+    pub key: Option<CommentsKey>,
+}
+
+#[derive(Debug)]
+pub enum ReplyStatus {
+    Sending,
+    Failed(String),
+}
+
+/// A private message the user wants sent.
+#[derive(Debug, Clone)]
+pub struct OutgoingMessage {
+    pub to: String,
+    pub subject: String,
+    pub text: String,
+    /// The message replied to, and the conversation it belongs to. `None` for new conversations.
+    pub reply_to: Option<(String, String)>,
+}
+
+/// What a new post links to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PostKind {
+    Text,
+    Link,
+    Image,
+}
+
+/// A post the user wants submitted.
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub subreddit: String,
+    pub title: String,
+    pub kind: PostKind,
+    /// Body of text posts, url of link posts and path of the image of image posts.
+    pub content: String,
+    pub flair_id: Option<String>,
+    pub nsfw: bool,
+    pub spoiler: bool,
+}
+
+/// A link flair that can be chosen for posts in a subreddit.
+#[derive(Debug, Clone)]
+pub struct Flair {
+    pub id: String,
+    pub text: String,
+}
+
+/// A reason reddit rejected a submission.
+#[derive(Debug, Clone)]
+pub struct SubmitError {
+    /// The form field the error is about, as reddit names it (`sr`, `title`, `text`, `url`, ..).
+    pub field: Option<String>,
+    pub message: String,
+}
+
+#[derive(Debug)]
+pub enum SubmitStatus {
+    Sending,
+    Failed(Vec<SubmitError>),
+    /// The post was submitted, and the form can be cleared.
+    Done,
+}
+
+/// Floatable, potentially open, windows.
+#[derive(Debug)]
+pub struct Windows {
+    windows: Vec<Box<dyn Show>>,
+}
+
+impl Windows {
+    pub fn new() -> Self {
+        Self {
+            windows: vec![
+                Box::new(SubredditWindow::new()),
+                Box::new(FilterWindow::new()),
+                Box::new(ComposerWindow::new()),
+                Box::new(SubmitWindow::new()),
+                Box::new(InboxWindow::new()),
+                Box::new(MessagesWindow::new()),
+                Box::new(AccountsWindow::new()),
+                Box::new(LoginWindow::new()),
+                Box::new(SortWindow::new()),
+                Box::new(SearchWindow::new()),
+            ],
+        }
+    }
+
+    pub fn open(&mut self, kind: WindowKind) {
+        let window = self
+            .windows
+            .iter_mut()
+            .find(|window| window.kind() == kind)
+            .expect("Uninitialized window");
+
+        window.toggle_open();
+    }
+
+    /// Like [`Self::open`], but leaves the window open if it already is.
+    pub fn ensure_open(&mut self, kind: WindowKind) {
+        let is_open = self
+            .windows
+            .iter()
+            .any(|window| window.kind() == kind && window.is_open());
+
+        if !is_open {
+            self.open(kind);
+        }
+    }
+
+    /// Called every frame
+    pub fn update(&mut self, ctx: &CtxRef, reddit: &Reddit, state: &mut State) {
+        for window in self.windows.iter_mut() {
+            window.show(ctx, reddit, state)
+        }
+    }
+}
+
+#[derive(Debug, Eq, PartialEq)]
+pub enum WindowKind {
+    Subreddit,
+    Filter,
+    Composer,
+    Submit,
+    Inbox,
+    Messages,
+    Accounts,
+    Login,
+    Sort,
+    Search,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WindowState {
+    open: bool,
+    request_focus: bool,
+}
+
+impl WindowState {
+    fn new() -> Self {
+        Self {
+            open: false,
+            request_focus: true,
+        }
+    }
+}
+
+/// Keep [`State::num_request_disable_binds`] in sync with whether `claimed` should hold a claim,
+/// like a window whose text field has focus.
+fn set_claim(claimed: &mut bool, claim: bool, num_request_disable_binds: &mut u32) {
+    if claim != *claimed {
+        if claim {
+            *num_request_disable_binds += 1;
+        } else {
+            *num_request_disable_binds -= 1;
+        }
+        *claimed = claim;
+    }
+}
+
+pub trait Show: std::fmt::Debug {
+    fn show(&mut self, ctx: &egui::CtxRef, reddit: &Reddit, state: &mut State);
+    fn kind(&self) -> WindowKind;
+    fn toggle_open(&mut self);
+    fn is_open(&self) -> bool;
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FilterWindow {
+    window: WindowState,
+}
+
+impl FilterWindow {
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+        }
+    }
+}
+
+impl Show for FilterWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, _reddit: &Reddit, state: &mut State) {
+        let mut should_close = false;
+
+        if !self.window.open {
+            self.window.request_focus = true;
+        }
+
+        egui::Window::new("Filters")
+            .open(&mut self.window.open)
+            .title_bar(state.options.show_title_bars)
+            .show(ctx, |ui| {
+                ui.add_space(10f32);
+
+                // The filters belong to the tab, so the checkbox shows the active tab's.
+                let mut checked = state.active_filters.contains_key(&0);
+                let inner = ui.horizontal(|ui| {
+                    ui.label("Posts: ");
+                    ui.checkbox(&mut checked, "Only renderable")
+                });
+                let response = inner.response;
+
+                if inner.inner.changed() {
+                    if checked {
+                        state
+                            .active_filters
+                            .insert(0, |p| p.content_post().selftext.is_some() || p.is_image());
+                    } else {
+                        state.active_filters.remove(&0);
+                    }
+                }
+
+                ui.add_space(10f32);
+
+                if self.window.request_focus {
+                    response.request_focus();
+                    self.window.request_focus = false;
+                }
+
+                if response.gained_focus() {
+                    state.num_request_disable_binds += 1
+                }
+
+                if response.lost_focus() {
+                    state.num_request_disable_binds -= 1;
+
+                    if ui.input().key_pressed(egui::Key::Enter) {
+                        println!("Enter!");
+                        should_close = true;
+                    }
+                }
+            });
+
+        if should_close {
+            self.window.open = false;
+        }
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Filter
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+/// Opens a subreddit by name, suggesting names as they are typed.
+#[derive(Debug)]
+pub struct SubredditWindow {
+    window: WindowState,
+    /// Whether the name field has focus, and thus disables keybinds.
+    has_focus: bool,
+    text: String,
+    /// Suggestion picked with the arrow keys, `None` for the typed name.
+    selected: Option<usize>,
+    /// When the name was edited, until reddit is asked to complete it.
+    edited: Option<Instant>,
+}
+
+impl SubredditWindow {
+    const MAX_SUGGESTIONS: usize = 8;
+    /// How long typing has to pause before reddit is asked to complete the name.
+    const AUTOCOMPLETE_DELAY: Duration = Duration::from_millis(300);
+
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+            has_focus: false,
+            text: String::new(),
+            selected: None,
+            edited: None,
+        }
+    }
+
+    fn name(&self) -> String {
+        self.text
+            .trim()
+            .trim_start_matches('/')
+            .trim_start_matches("r/")
+            .to_owned()
+    }
+
+    /// Returns whether the name field has focus.
+    fn ui(&mut self, ui: &mut egui::Ui, reddit: &Reddit, state: &mut State) -> bool {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.text)
+                .hint_text("Subreddit")
+                .desired_width(f32::INFINITY),
+        );
+        let focused = response.has_focus();
+        let enter = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+
+        if self.window.request_focus {
+            response.request_focus();
+            self.window.request_focus = false;
+        }
+
+        if response.changed() {
+            self.selected = None;
+            self.edited = Some(Instant::now());
+        }
+
+        let name = self.name();
+
+        if let Some(edited) = self.edited {
+            if edited.elapsed() < Self::AUTOCOMPLETE_DELAY {
+                ui.ctx().request_repaint();
+            } else {
+                // Reddit has little to suggest for a single character.
+                if name.chars().count() >= 2 {
+                    state.request_subreddit_autocomplete(&name);
+                }
+                self.edited = None;
+            }
+        }
+
+        let suggestions = state.subreddit_suggestions(&name, Self::MAX_SUGGESTIONS);
+        let last = suggestions.len().checked_sub(1);
+        self.selected = self.selected.filter(|&idx| idx < suggestions.len());
+
+        // Moving past either end goes back to the typed name.
+        if focused && ui.input().key_pressed(egui::Key::ArrowDown) {
+            self.selected = match self.selected {
+                None => last.map(|_| 0),
+                Some(idx) => Some(idx + 1).filter(|&next| next < suggestions.len()),
+            };
+        }
+        if focused && ui.input().key_pressed(egui::Key::ArrowUp) {
+            self.selected = match self.selected {
+                None => last,
+                Some(idx) => idx.checked_sub(1),
+            };
+        }
+
+        let mut chosen = None;
+
+        for (idx, suggestion) in suggestions.iter().enumerate() {
+            let label =
+                ui.selectable_label(self.selected == Some(idx), format!("/r/{}", suggestion));
+            if label.clicked() {
+                chosen = Some(suggestion.clone());
+            }
+        }
+
+        if enter {
+            chosen = self
+                .selected
+                .and_then(|idx| suggestions.get(idx).cloned())
+                .or_else(|| Some(name).filter(|name| !name.is_empty()));
+            // Typing on is likely if the subreddit can't be opened.
+            self.window.request_focus = true;
+        }
+
+        if let Some(chosen) = chosen {
+            self.text = chosen.clone();
+            self.selected = None;
+            state.check_subreddit(reddit, chosen);
+        }
+
+        match &state.subreddit_check {
+            Some(SubredditCheck { name, result: None }) => {
+                ui.label(format!("Opening /r/{}..", name));
+            }
+            Some(SubredditCheck {
+                result: Some(Err(err)),
+                ..
+            }) => {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            _ => {}
+        }
+
+        focused
+    }
+}
+
+impl Show for SubredditWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, reddit: &Reddit, state: &mut State) {
+        if !self.window.open {
+            self.window.request_focus = true;
+            set_claim(
+                &mut self.has_focus,
+                false,
+                &mut state.num_request_disable_binds,
+            );
+            self.text.clear();
+            self.selected = None;
+            self.edited = None;
+            // Closing the window stops waiting for the subreddit being opened.
+            state.subreddit_check = None;
+            return;
+        }
+
+        let mut open = self.window.open;
+
+        let focused = egui::Window::new("Choose subreddit")
+            .open(&mut open)
+            .title_bar(state.options.show_title_bars)
+            .show(ctx, |ui| self.ui(ui, reddit, state))
+            .and_then(|response| response.inner)
+            .unwrap_or(false);
+
+        if let Some(SubredditCheck {
+            result: Some(Ok(())),
+            ..
+        }) = state.subreddit_check
+        {
+            open = false;
+        }
+
+        self.window.open = open;
+        set_claim(
+            &mut self.has_focus,
+            focused && open,
+            &mut state.num_request_disable_binds,
+        );
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Subreddit
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+/// Editor for replies to posts and comments.
+#[derive(Debug)]
+pub struct ComposerWindow {
+    window: WindowState,
+    /// Whether the editor has focus, and thus disables keybinds.
+    has_focus: bool,
+    /// Rendered preview, and the text it was rendered from.
+    preview: Option<(String, SelfText)>,
+}
+
+impl ComposerWindow {
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+            has_focus: false,
+            preview: None,
+        }
+    }
+}
+
+impl Show for ComposerWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, _reddit: &Reddit, state: &mut State) {
+        let target = match state.reply_target.clone() {
+            Some(target) if self.window.open => target,
+            _ => {
+                self.window.open = false;
+                self.window.request_focus = true;
+                set_claim(
+                    &mut self.has_focus,
+                    false,
+                    &mut state.num_request_disable_binds,
+                );
+
+                if let Some(ReplyStatus::Failed(_)) = state.reply_status {
+                    state.reply_status = None;
+                }
+                return;
+            }
+        };
+
+        egui::Window::new(format!("Reply to {}", target.title))
+            .id(egui::Id::new("composer"))
+            .open(&mut self.window.open)
+            .title_bar(state.options.show_title_bars)
+            .default_width(600f32)
+            .show(ctx, |ui| {
+                let draft = state.drafts.entry(target.name.clone()).or_default();
+
+                let response = ui.add(
+                    egui::TextEdit::multiline(draft)
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("Markdown"),
+                );
 
                 if self.window.request_focus {
                     response.request_focus();
                     self.window.request_focus = false;
                 }
 
-                if response.gained_focus() {
-                    state.num_request_disable_binds += 1
+                set_claim(
+                    &mut self.has_focus,
+                    response.has_focus(),
+                    &mut state.num_request_disable_binds,
+                );
+
+                let sending = matches!(state.reply_status, Some(ReplyStatus::Sending));
+
+                ui.horizontal(|ui| {
+                    let submit = egui::Button::new("Submit");
+                    if ui
+                        .add_enabled(!sending && !draft.trim().is_empty(), submit)
+                        .clicked()
+                    {
+                        state.pending_reply = Some((target.clone(), draft.clone()));
+                        state.reply_status = Some(ReplyStatus::Sending);
+                    }
+
+                    match &state.reply_status {
+                        Some(ReplyStatus::Sending) => {
+                            ui.label("Sending..");
+                        }
+                        Some(ReplyStatus::Failed(err)) => {
+                            ui.colored_label(egui::Color32::RED, err);
+                        }
+                        None => {}
+                    }
+                });
+
+                ui.separator();
+
+                let stale = self
+                    .preview
+                    .as_ref()
+                    .is_none_or(|(text, _)| text.as_str() != draft.as_str());
+                if stale {
+                    let preview = SelfText::new(draft, &state.highlighter);
+                    self.preview = Some((draft.clone(), preview));
+                }
+
+                if let Some((_, preview)) = &self.preview {
+                    // Links in the preview are not followed.
+                    let _ = preview.render(ui);
+                }
+            });
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Composer
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+/// Form for submitting new posts.
+#[derive(Debug)]
+pub struct SubmitWindow {
+    window: WindowState,
+    /// Whether a text field has focus, and thus disables keybinds.
+    has_focus: bool,
+    subreddit: String,
+    /// Whether subreddit suggestions are shown. The subreddit field loses focus on the frame a
+    /// suggestion is clicked, so they stay until one is picked or something else is used.
+    suggesting: bool,
+    title: String,
+    kind: PostKind,
+    body: String,
+    url: String,
+    image_path: String,
+    flair_id: Option<String>,
+    nsfw: bool,
+    spoiler: bool,
+    /// Rendered preview, and the text it was rendered from.
+    preview: Option<(String, SelfText)>,
+}
+
+impl SubmitWindow {
+    const MAX_SUGGESTIONS: usize = 5;
+
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+            has_focus: false,
+            subreddit: String::new(),
+            suggesting: false,
+            title: String::new(),
+            kind: PostKind::Text,
+            body: String::new(),
+            url: String::new(),
+            image_path: String::new(),
+            flair_id: None,
+            nsfw: false,
+            spoiler: false,
+            preview: None,
+        }
+    }
+
+    fn subreddit_name(&self) -> String {
+        self.subreddit.trim().trim_start_matches("r/").to_owned()
+    }
+
+    fn submission(&self) -> Submission {
+        let content = match self.kind {
+            PostKind::Text => &self.body,
+            PostKind::Link => &self.url,
+            PostKind::Image => &self.image_path,
+        };
+
+        Submission {
+            subreddit: self.subreddit_name(),
+            title: self.title.trim().to_owned(),
+            kind: self.kind,
+            content: content.trim().to_owned(),
+            flair_id: self.flair_id.clone(),
+            nsfw: self.nsfw,
+            spoiler: self.spoiler,
+        }
+    }
+
+    /// Show the errors reddit reported for `field`, if any.
+    fn field_errors(ui: &mut egui::Ui, errors: &[SubmitError], field: &str) {
+        for error in errors
+            .iter()
+            .filter(|error| error.field.as_deref() == Some(field))
+        {
+            ui.colored_label(egui::Color32::RED, &error.message);
+        }
+    }
+
+    /// Returns whether a text field has focus.
+    fn form(&mut self, ui: &mut egui::Ui, state: &mut State) -> bool {
+        let errors = match &state.submit_status {
+            Some(SubmitStatus::Failed(errors)) => errors.clone(),
+            _ => vec![],
+        };
+        let mut focused = false;
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.subreddit)
+                .hint_text("Subreddit")
+                .desired_width(f32::INFINITY),
+        );
+        focused |= response.has_focus();
+
+        if self.window.request_focus {
+            response.request_focus();
+            self.window.request_focus = false;
+        }
+
+        if response.has_focus() {
+            self.suggesting = true;
+        }
+
+        let name = self.subreddit_name();
+        let mut chosen = None;
+
+        if self.suggesting && !name.is_empty() {
+            let needle = name.to_lowercase();
+            let suggestions = state
+                .subscriptions_component
+                .known()
+                .filter(|known| {
+                    known.to_lowercase().starts_with(&needle) && !known.eq_ignore_ascii_case(&name)
+                })
+                .take(Self::MAX_SUGGESTIONS);
+
+            for suggestion in suggestions {
+                if ui.selectable_label(false, suggestion).clicked() {
+                    chosen = Some(suggestion.to_owned());
+                }
+            }
+        }
+
+        if let Some(chosen) = chosen {
+            self.subreddit = chosen;
+            self.suggesting = false;
+        } else if !response.has_focus()
+            && (ui.input().pointer.any_click() || ui.memory().focus().is_some())
+        {
+            self.suggesting = false;
+        }
+
+        // Flairs are fetched once the subreddit is chosen, not on every keystroke.
+        if !response.has_focus() && !name.is_empty() {
+            state.request_link_flairs(&name);
+        }
+
+        Self::field_errors(ui, &errors, "sr");
+
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.title)
+                .hint_text("Title")
+                .desired_width(f32::INFINITY),
+        );
+        focused |= response.has_focus();
+        Self::field_errors(ui, &errors, "title");
+
+        ui.horizontal(|ui| {
+            ui.selectable_value(&mut self.kind, PostKind::Text, "Text");
+            ui.selectable_value(&mut self.kind, PostKind::Link, "Link");
+            ui.selectable_value(&mut self.kind, PostKind::Image, "Image");
+        });
+
+        match self.kind {
+            PostKind::Text => {
+                let response = ui.add(
+                    egui::TextEdit::multiline(&mut self.body)
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY)
+                        .hint_text("Markdown"),
+                );
+                focused |= response.has_focus();
+                Self::field_errors(ui, &errors, "text");
+            }
+            PostKind::Link => {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.url)
+                        .hint_text("Url")
+                        .desired_width(f32::INFINITY),
+                );
+                focused |= response.has_focus();
+                Self::field_errors(ui, &errors, "url");
+            }
+            PostKind::Image => {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.image_path)
+                        .hint_text("Path to image")
+                        .desired_width(f32::INFINITY),
+                );
+                focused |= response.has_focus();
+                Self::field_errors(ui, &errors, "url");
+            }
+        }
+
+        ui.horizontal(|ui| {
+            let flairs = state.link_flairs.get(&name.to_lowercase());
+
+            match flairs {
+                Some(flairs) if !flairs.is_empty() => {
+                    let selected = self
+                        .flair_id
+                        .as_ref()
+                        .and_then(|id| flairs.iter().find(|flair| &flair.id == id))
+                        .map_or("No flair", |flair| flair.text.as_str());
+
+                    egui::ComboBox::from_id_source("submit_flair")
+                        .selected_text(selected)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.flair_id, None, "No flair");
+                            for flair in flairs {
+                                ui.selectable_value(
+                                    &mut self.flair_id,
+                                    Some(flair.id.clone()),
+                                    &flair.text,
+                                );
+                            }
+                        });
+                }
+                _ => self.flair_id = None,
+            }
+
+            ui.checkbox(&mut self.nsfw, "NSFW");
+            ui.checkbox(&mut self.spoiler, "Spoiler");
+        });
+        Self::field_errors(ui, &errors, "flair");
+
+        let sending = matches!(state.submit_status, Some(SubmitStatus::Sending));
+
+        ui.horizontal(|ui| {
+            let submission = self.submission();
+            let complete = !submission.subreddit.is_empty()
+                && !submission.title.is_empty()
+                && (self.kind == PostKind::Text || !submission.content.is_empty());
+
+            if ui
+                .add_enabled(!sending && complete, egui::Button::new("Submit"))
+                .clicked()
+            {
+                state.pending_submission = Some(submission);
+                state.submit_status = Some(SubmitStatus::Sending);
+            }
+
+            if sending {
+                ui.label("Sending..");
+            }
+        });
+
+        // Errors that are not about a single field.
+        for error in errors.iter().filter(|error| {
+            !matches!(
+                error.field.as_deref(),
+                Some("sr" | "title" | "text" | "url" | "flair")
+            )
+        }) {
+            ui.colored_label(egui::Color32::RED, &error.message);
+        }
+
+        if self.kind == PostKind::Text && !self.body.is_empty() {
+            ui.separator();
+
+            let stale = self
+                .preview
+                .as_ref()
+                .is_none_or(|(text, _)| text != &self.body);
+            if stale {
+                let preview = SelfText::new(&self.body, &state.highlighter);
+                self.preview = Some((self.body.clone(), preview));
+            }
+
+            if let Some((_, preview)) = &self.preview {
+                // Links in the preview are not followed.
+                let _ = preview.render(ui);
+            }
+        }
+
+        focused
+    }
+}
+
+impl Show for SubmitWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, _reddit: &Reddit, state: &mut State) {
+        if let Some(SubmitStatus::Done) = state.submit_status {
+            state.submit_status = None;
+            set_claim(
+                &mut self.has_focus,
+                false,
+                &mut state.num_request_disable_binds,
+            );
+            *self = Self::new();
+            return;
+        }
+
+        if !self.window.open {
+            self.window.request_focus = true;
+            set_claim(
+                &mut self.has_focus,
+                false,
+                &mut state.num_request_disable_binds,
+            );
+
+            if let Some(SubmitStatus::Failed(_)) = state.submit_status {
+                state.submit_status = None;
+            }
+            return;
+        }
+
+        let mut open = self.window.open;
+
+        let focused = egui::Window::new("Submit post")
+            .open(&mut open)
+            .title_bar(state.options.show_title_bars)
+            .default_width(600f32)
+            .show(ctx, |ui| self.form(ui, state))
+            .and_then(|response| response.inner)
+            .unwrap_or(false);
+
+        self.window.open = open;
+        set_claim(
+            &mut self.has_focus,
+            focused && open,
+            &mut state.num_request_disable_binds,
+        );
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Submit
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+/// Private messages, comment replies and mentions of the logged in user.
+#[derive(Debug)]
+pub struct InboxWindow {
+    window: WindowState,
+    /// Show only unread items.
+    only_unread: bool,
+}
+
+impl InboxWindow {
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+            only_unread: false,
+        }
+    }
+
+    /// Returns whether the item should be marked read, and whether its conversation should be opened.
+    fn ui_item(ctx: &egui::CtxRef, ui: &mut egui::Ui, item: &InboxItem) -> (bool, bool) {
+        let mut mark_read = false;
+        let mut open_conversation = false;
+
+        ui.horizontal(|ui| {
+            let title =
+                egui::Label::new(format!("{}: {}", item.kind.name(), item.subject)).strong();
+            ui.add(if item.unread {
+                title.text_color(egui::Color32::from_rgb(255, 69, 0))
+            } else {
+                title
+            });
+
+            ui.label(format!(
+                "from /u/{}, {}",
+                item.author.as_deref().unwrap_or("[deleted]"),
+                format_age(item.created_utc)
+            ));
+        });
+
+        ui.label(&item.body);
+
+        ui.horizontal(|ui| {
+            if item.kind == InboxKind::Message {
+                if ui.small_button("Conversation").clicked() {
+                    open_conversation = true;
+                }
+            } else if ui.small_button("Context").clicked() {
+                ctx.output().open_url(item.context_url());
+                mark_read = true;
+            }
+
+            if item.unread && ui.small_button("Mark read").clicked() {
+                mark_read = true;
+            }
+        });
+
+        ui.separator();
+
+        (mark_read && item.unread, open_conversation)
+    }
+}
+
+impl Show for InboxWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, _reddit: &Reddit, state: &mut State) {
+        let only_unread = &mut self.only_unread;
+        let mut read = vec![];
+        let mut open_conversation = None;
+
+        egui::Window::new("Inbox")
+            .open(&mut self.window.open)
+            .title_bar(state.options.show_title_bars)
+            .default_width(500f32)
+            .default_height(600f32)
+            .show(ctx, |ui| {
+                let inbox = match &state.inbox {
+                    Some(inbox) => inbox,
+                    None => {
+                        ui.label("Loading..");
+                        return;
+                    }
+                };
+
+                ui.horizontal(|ui| {
+                    ui.checkbox(only_unread, "Only unread");
+
+                    let unread: Vec<String> = inbox
+                        .iter()
+                        .filter(|item| item.unread)
+                        .map(|item| item.name.clone())
+                        .collect();
+
+                    if ui
+                        .add_enabled(!unread.is_empty(), egui::Button::new("Mark all read"))
+                        .clicked()
+                    {
+                        read = unread;
+                    }
+                });
+
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for item in inbox.iter().filter(|item| item.unread || !*only_unread) {
+                            let (mark_read, open) = Self::ui_item(ctx, ui, item);
+                            if mark_read {
+                                read.push(item.name.clone());
+                            }
+                            if open {
+                                open_conversation = Some(item.conversation.clone());
+                            }
+                        }
+
+                        if inbox.is_empty() {
+                            ui.label("Your inbox is empty.");
+                        }
+                    });
+            });
+
+        if !read.is_empty() {
+            state.mark_read(read);
+        }
+
+        if open_conversation.is_some() {
+            state.open_conversation = open_conversation;
+        }
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Inbox
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+/// Private message conversations, and a composer for new ones. While open, its own keybinds
+/// replace the others.
+#[derive(Debug)]
+pub struct MessagesWindow {
+    window: WindowState,
+    /// Whether the window holds a claim on [`State::num_request_disable_binds`] for being open.
+    claims_binds: bool,
+    /// Whether a text field has focus, which also disables the window's own keybinds.
+    has_focus: bool,
+    /// Fullname of the first message of the shown conversation.
+    selected: Option<String>,
+    /// Show the composer for a new conversation instead.
+    composing: bool,
+    to: String,
+    subject: String,
+    text: String,
+    /// A text field should take focus next frame.
+    focus_text: bool,
+    /// The composed message was sent, and the composer should be cleared once reddit accepts it.
+    awaiting_sent: bool,
+}
+
+impl MessagesWindow {
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+            claims_binds: false,
+            has_focus: false,
+            selected: None,
+            composing: false,
+            to: String::new(),
+            subject: String::new(),
+            text: String::new(),
+            focus_text: false,
+            awaiting_sent: false,
+        }
+    }
+
+    /// The window's own keybinds. Ignored while typing.
+    fn actions(&self, ctx: &egui::CtxRef, state: &State) -> Vec<MessageAction> {
+        if self.has_focus {
+            return vec![];
+        }
+
+        ctx.input()
+            .events
+            .iter()
+            .filter_map(|event| match event {
+                // Pressed rather than released, so the Escape that ends typing does not also close the window.
+                egui::Event::Key {
+                    key,
+                    pressed: true,
+                    modifiers,
+                } if modifiers.is_none() => {
+                    state.options.message_binds.get(&Key::from(*key)).copied()
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn handle_action(&mut self, action: MessageAction, conversations: &[inbox::Conversation]) {
+        let idx = self.selected.as_ref().and_then(|selected| {
+            conversations
+                .iter()
+                .position(|conversation| conversation.name == selected.as_str())
+        });
+
+        let select = |idx: usize| conversations.get(idx).map(|c| c.name.to_owned());
+
+        match action {
+            MessageAction::ConversationUp => {
+                if let Some(name) = idx.and_then(|idx| idx.checked_sub(1)).and_then(select) {
+                    self.selected = Some(name);
+                    self.composing = false;
+                }
+            }
+            MessageAction::ConversationDown => {
+                if let Some(name) = select(idx.map_or(0, |idx| idx + 1)) {
+                    self.selected = Some(name);
+                    self.composing = false;
+                }
+            }
+            MessageAction::Reply => {
+                self.composing = false;
+                self.focus_text = self.selected.is_some();
+            }
+            MessageAction::Compose => {
+                self.composing = true;
+                self.focus_text = true;
+            }
+            MessageAction::Close => self.window.open = false,
+        }
+    }
+
+    /// Returns whether a text field has focus.
+    fn conversation(
+        &mut self,
+        ui: &mut egui::Ui,
+        conversation: &inbox::Conversation,
+        state: &mut State,
+    ) -> bool {
+        ui.heading(conversation.subject);
+        if let Some(partner) = conversation.partner {
+            ui.label(format!("with /u/{}", partner));
+        }
+        ui.separator();
+
+        egui::ScrollArea::vertical()
+            .id_source("conversation_scroller")
+            .max_height(ui.available_height() - 160f32)
+            .auto_shrink([false, false])
+            .show(ui, |ui| {
+                for message in &conversation.messages {
+                    let author = message.author.as_deref().unwrap_or("[deleted]");
+                    ui.add(
+                        egui::Label::new(format!(
+                            "/u/{}, {}",
+                            author,
+                            format_age(message.created_utc)
+                        ))
+                        .strong(),
+                    );
+                    ui.label(&message.body);
+                    ui.add_space(6f32);
+                }
+            });
+
+        ui.separator();
+
+        let draft = state
+            .drafts
+            .entry(conversation.name.to_owned())
+            .or_default();
+
+        let response = ui.add(
+            egui::TextEdit::multiline(draft)
+                .desired_rows(4)
+                .desired_width(f32::INFINITY)
+                .hint_text("Reply"),
+        );
+
+        if std::mem::take(&mut self.focus_text) {
+            response.request_focus();
+        }
+
+        let text = draft.clone();
+        let sending = matches!(state.message_status, Some(ReplyStatus::Sending));
+
+        ui.horizontal(|ui| {
+            let send = egui::Button::new("Send");
+            if ui
+                .add_enabled(!sending && !text.trim().is_empty(), send)
+                .clicked()
+            {
+                let last = conversation.messages.last().map(|m| m.name.clone());
+
+                state.pending_message = Some(OutgoingMessage {
+                    to: conversation.partner.unwrap_or_default().to_owned(),
+                    subject: conversation.subject.to_owned(),
+                    text,
+                    reply_to: last.map(|last| (last, conversation.name.to_owned())),
+                });
+                state.message_status = Some(ReplyStatus::Sending);
+            }
+
+            Self::ui_status(ui, state);
+        });
+
+        response.has_focus()
+    }
+
+    /// Returns whether a text field has focus.
+    fn composer(&mut self, ui: &mut egui::Ui, state: &mut State) -> bool {
+        ui.heading("New message");
+        ui.separator();
+
+        let to = ui.add(
+            egui::TextEdit::singleline(&mut self.to)
+                .hint_text("To")
+                .desired_width(f32::INFINITY),
+        );
+        let subject = ui.add(
+            egui::TextEdit::singleline(&mut self.subject)
+                .hint_text("Subject")
+                .desired_width(f32::INFINITY),
+        );
+        let text = ui.add(
+            egui::TextEdit::multiline(&mut self.text)
+                .desired_rows(8)
+                .desired_width(f32::INFINITY)
+                .hint_text("Markdown"),
+        );
+
+        if std::mem::take(&mut self.focus_text) {
+            to.request_focus();
+        }
+
+        let sending = matches!(state.message_status, Some(ReplyStatus::Sending));
+        let recipient = self
+            .to
+            .trim()
+            .trim_start_matches("u/")
+            .trim_start_matches("/u/");
+        let complete = !recipient.is_empty()
+            && !self.subject.trim().is_empty()
+            && !self.text.trim().is_empty();
+
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(!sending && complete, egui::Button::new("Send"))
+                .clicked()
+            {
+                state.pending_message = Some(OutgoingMessage {
+                    to: recipient.to_owned(),
+                    subject: self.subject.trim().to_owned(),
+                    text: self.text.clone(),
+                    reply_to: None,
+                });
+                state.message_status = Some(ReplyStatus::Sending);
+                self.awaiting_sent = true;
+            }
+
+            Self::ui_status(ui, state);
+        });
+
+        to.has_focus() || subject.has_focus() || text.has_focus()
+    }
+
+    fn ui_status(ui: &mut egui::Ui, state: &State) {
+        match &state.message_status {
+            Some(ReplyStatus::Sending) => {
+                ui.label("Sending..");
+            }
+            Some(ReplyStatus::Failed(err)) => {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            None => {}
+        }
+    }
+}
+
+impl Show for MessagesWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, _reddit: &Reddit, state: &mut State) {
+        if let Some(conversation) = state.open_conversation.take() {
+            self.selected = Some(conversation);
+            self.composing = false;
+        }
+
+        if !self.window.open {
+            set_claim(
+                &mut self.has_focus,
+                false,
+                &mut state.num_request_disable_binds,
+            );
+            set_claim(
+                &mut self.claims_binds,
+                false,
+                &mut state.num_request_disable_binds,
+            );
+
+            if let Some(ReplyStatus::Failed(_)) = state.message_status {
+                state.message_status = None;
+            }
+            return;
+        }
+
+        set_claim(
+            &mut self.claims_binds,
+            true,
+            &mut state.num_request_disable_binds,
+        );
+
+        if state.sent_messages.is_none() && !state.pending_sent_fetch {
+            state.pending_sent_fetch = true;
+        }
+
+        // The conversations borrow the inbox, which the window needs to mark messages read.
+        let received = state.inbox.clone().unwrap_or_default();
+        let sent = state.sent_messages.clone().unwrap_or_default();
+        let conversations = inbox::conversations(&received, &sent);
+
+        for action in self.actions(ctx, state) {
+            self.handle_action(action, &conversations);
+        }
+
+        if self.awaiting_sent {
+            match state.message_status {
+                Some(ReplyStatus::Sending) => {}
+                Some(ReplyStatus::Failed(_)) => self.awaiting_sent = false,
+                None => {
+                    self.awaiting_sent = false;
+                    self.to.clear();
+                    self.subject.clear();
+                    self.text.clear();
+                }
+            }
+        }
+
+        let mut open = self.window.open;
+
+        let focused = egui::Window::new("Messages")
+            .open(&mut open)
+            .title_bar(state.options.show_title_bars)
+            .default_width(700f32)
+            .default_height(500f32)
+            .show(ctx, |ui| {
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        ui.set_width(200f32);
+
+                        if ui.button("New message").clicked() {
+                            self.composing = true;
+                            self.focus_text = true;
+                        }
+                        ui.separator();
+
+                        egui::ScrollArea::vertical()
+                            .id_source("conversations_scroller")
+                            .auto_shrink([false, false])
+                            .show(ui, |ui| {
+                                for conversation in &conversations {
+                                    let selected = !self.composing
+                                        && self.selected.as_deref() == Some(conversation.name);
+                                    let unread = if conversation.has_unread() {
+                                        "● "
+                                    } else {
+                                        ""
+                                    };
+                                    let text = format!(
+                                        "{}{}\n/u/{}",
+                                        unread,
+                                        conversation.subject,
+                                        conversation.partner.unwrap_or("[deleted]")
+                                    );
+
+                                    if ui.selectable_label(selected, text).clicked() {
+                                        self.selected = Some(conversation.name.to_owned());
+                                        self.composing = false;
+                                    }
+                                }
+
+                                if conversations.is_empty() {
+                                    ui.label("No messages.");
+                                }
+                            });
+                    });
+
+                    ui.separator();
+
+                    ui.vertical(|ui| {
+                        let shown = self.selected.as_ref().and_then(|selected| {
+                            conversations
+                                .iter()
+                                .find(|conversation| conversation.name == selected.as_str())
+                        });
+
+                        match shown {
+                            Some(conversation) if !self.composing => {
+                                let unread: Vec<String> = conversation
+                                    .messages
+                                    .iter()
+                                    .filter(|message| message.unread)
+                                    .map(|message| message.name.clone())
+                                    .collect();
+                                if !unread.is_empty() {
+                                    state.mark_read(unread);
+                                }
+
+                                self.conversation(ui, conversation, state)
+                            }
+                            _ => self.composer(ui, state),
+                        }
+                    })
+                    .inner
+                })
+                .inner
+            })
+            .and_then(|response| response.inner)
+            .unwrap_or(false);
+
+        self.window.open = open;
+        set_claim(
+            &mut self.has_focus,
+            focused && open,
+            &mut state.num_request_disable_binds,
+        );
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Messages
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+/// Switch between, add and remove accounts.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AccountsWindow {
+    window: WindowState,
+}
+
+impl AccountsWindow {
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+        }
+    }
+}
+
+impl Show for AccountsWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, _reddit: &Reddit, state: &mut State) {
+        let mut request = None;
+
+        egui::Window::new("Accounts")
+            .open(&mut self.window.open)
+            .title_bar(state.options.show_title_bars)
+            .default_width(250f32)
+            .show(ctx, |ui| {
+                let accounts = &state.accounts;
+
+                for (idx, account) in accounts.accounts.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        let active = accounts.active == Some(idx);
+                        if ui
+                            .selectable_label(active, format!("/u/{}", account.name))
+                            .clicked()
+                            && !active
+                        {
+                            request = Some(AccountRequest::Switch(Some(account.name.clone())));
+                        }
+
+                        ui.with_layout(egui::Layout::right_to_left(), |ui| {
+                            if ui.small_button("✖").on_hover_text("Remove").clicked() {
+                                request = Some(AccountRequest::Remove(account.name.clone()));
+                            }
+                        });
+                    });
+                }
+
+                let anonymous = accounts.active.is_none();
+                if ui.selectable_label(anonymous, "Anonymous").clicked() && !anonymous {
+                    request = Some(AccountRequest::Switch(None));
                 }
 
-                if response.lost_focus() {
-                    state.num_request_disable_binds -= 1;
+                ui.separator();
 
-                    if ui.input().key_pressed(egui::Key::Enter) {
-                        println!("Enter!");
-                        should_close = true;
-                    }
+                if ui.button("Add account").clicked() {
+                    request = Some(AccountRequest::Add);
                 }
             });
 
-        if self.checked {
-            state.active_filters.insert(0, |p| {
-                p.inner.selftext.is_some()
-                    || p.inner.url.ends_with(".jpg")
-                    || p.inner.url.ends_with(".png")
-                    || p.inner.url.ends_with(".jpeg")
-            });
-        } else {
-            state.active_filters.remove(&0);
-        }
-
-        if should_close {
-            self.window.open = false;
+        if request.is_some() {
+            state.pending_account = request;
         }
     }
 
     fn kind(&self) -> WindowKind {
-        WindowKind::Filter
+        WindowKind::Accounts
     }
 
     fn toggle_open(&mut self) {
         self.window.open = !self.window.open
     }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-pub struct SubredditWindow {
+/// Progress of logging in, with the url to authorize SnUI at.
+#[derive(Debug)]
+pub struct LoginWindow {
     window: WindowState,
-    text: Option<String>,
 }
 
-impl SubredditWindow {
+impl LoginWindow {
     fn new() -> Self {
         Self {
             window: WindowState::new(),
-            text: None,
         }
     }
 }
 
-impl Show for SubredditWindow {
-    fn show(&mut self, ctx: &egui::CtxRef, reddit: &Reddit, state: &mut State) {
-        let mut should_close = false;
-
-        if !self.window.open {
-            self.window.request_focus = true;
-            self.text = None;
-        }
+impl Show for LoginWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, _reddit: &Reddit, state: &mut State) {
+        let mut request = None;
+        let mut close = false;
 
-        egui::Window::new("Choose subreddit")
+        egui::Window::new("Log in")
             .open(&mut self.window.open)
             .title_bar(state.options.show_title_bars)
+            .default_width(350f32)
             .show(ctx, |ui| {
-                let mut text = self.text.take().unwrap_or(String::new());
-                let response = ui.add(egui::TextEdit::singleline(&mut text));
+                let login = match &mut state.login {
+                    Some(login) => login,
+                    None => {
+                        if ui.button("Log in").clicked() {
+                            request = Some(AccountRequest::Add);
+                        }
+                        return;
+                    }
+                };
 
-                if self.window.request_focus {
-                    response.request_focus();
-                    self.window.request_focus = false;
+                if let (Some(url), false) = (&login.url, login.opened) {
+                    login.opened = true;
+                    ui.output().open_url(url);
                 }
 
-                if response.gained_focus() {
-                    state.num_request_disable_binds += 1
+                if let Some(url) = &login.url {
+                    ui.label("Authorize SnUI in your browser, or open this url:");
+
+                    // Edits to the copy are thrown away, it is only there to be selected.
+                    let mut text = url.clone();
+                    ui.add(egui::TextEdit::singleline(&mut text).desired_width(f32::INFINITY));
+
+                    ui.horizontal(|ui| {
+                        if ui.button("Copy").clicked() {
+                            ui.output().copied_text = url.clone();
+                        }
+                        if ui.button("Open in browser").clicked() {
+                            ui.output().open_url(url);
+                        }
+                    });
+
+                    ui.separator();
                 }
 
-                self.text = if response.lost_focus() {
-                    state.num_request_disable_binds -= 1;
+                match &login.status {
+                    LoginStatus::Waiting => {
+                        // Keeps the countdown going.
+                        ui.ctx().request_repaint();
 
-                    if ui.input().key_pressed(egui::Key::Enter) {
-                        state.reset_feed(reddit.subreddit(&text).hot());
-                        should_close = true;
-                        None
-                    } else {
-                        Some(text)
+                        let remaining = login.remaining().as_secs();
+                        ui.label(format!(
+                            "Waiting for authorization, {}:{:02} left",
+                            remaining / 60,
+                            remaining % 60
+                        ));
+
+                        if ui.button("Cancel").clicked() {
+                            request = Some(AccountRequest::CancelLogin);
+                        }
+                    }
+                    LoginStatus::FetchingUser => {
+                        ui.label("Authorized, fetching user...");
+                    }
+                    LoginStatus::Done(name) => {
+                        ui.label(format!("Logged in as /u/{}", name));
+
+                        if ui.button("Close").clicked() {
+                            close = true;
+                        }
+                    }
+                    LoginStatus::Failed(err) => {
+                        let text = format!("Logging in failed: {}", err);
+                        ui.colored_label(egui::Color32::RED, text);
+
+                        if ui.button("Try again").clicked() {
+                            request = Some(AccountRequest::Add);
+                        }
+                    }
+                    LoginStatus::Cancelled => {
+                        ui.label("Login cancelled");
+
+                        if ui.button("Try again").clicked() {
+                            request = Some(AccountRequest::Add);
+                        }
                     }
-                } else {
-                    Some(text)
                 }
             });
 
-        if should_close {
+        if close {
             self.window.open = false;
         }
+
+        if request.is_some() {
+            state.pending_account = request;
+        }
     }
 
     fn kind(&self) -> WindowKind {
-        WindowKind::Subreddit
+        WindowKind::Login
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+/// Buttons for each of `kinds` and each time range. Returns the sort that was clicked, if any.
+fn pick_sort<K: SortOrder>(ui: &mut egui::Ui, sort: Sort<K>, kinds: &[K]) -> Option<Sort<K>> {
+    let mut chosen = None;
+
+    ui.horizontal_wrapped(|ui| {
+        for &kind in kinds {
+            if ui
+                .selectable_label(sort.kind == kind, kind.name())
+                .clicked()
+            {
+                chosen = Some(Sort { kind, ..sort });
+            }
+        }
+    });
+
+    ui.add_enabled_ui(sort.kind.has_range(), |ui| {
+        ui.label("From the past");
+
+        ui.horizontal_wrapped(|ui| {
+            for range in TimeRange::ALL {
+                if ui
+                    .selectable_label(sort.range == range, range.name())
+                    .clicked()
+                {
+                    chosen = Some(Sort { range, ..sort });
+                }
+            }
+        });
+    });
+
+    chosen
+}
+
+/// Picks the sort and time range of the shown feed.
+#[derive(Debug)]
+pub struct SortWindow {
+    window: WindowState,
+}
+
+impl SortWindow {
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+        }
+    }
+}
+
+impl Show for SortWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, reddit: &Reddit, state: &mut State) {
+        let current = state.sort();
+        let search = state.source.search_sort();
+        let mut chosen = None;
+        let mut chosen_search = None;
+
+        egui::Window::new("Sort")
+            .open(&mut self.window.open)
+            .title_bar(state.options.show_title_bars)
+            .show(ctx, |ui| {
+                if let Some(sort) = search {
+                    ui.label(format!("Sort {} by", state.source));
+                    chosen_search = pick_sort(ui, sort, &SearchSort::ALL);
+                    return;
+                }
+
+                let sort = match current {
+                    Some(sort) => sort,
+                    None => {
+                        ui.label(format!("{} can't be sorted", state.source));
+                        return;
+                    }
+                };
+
+                ui.label(format!("Sort {} by", state.source));
+                chosen = pick_sort(ui, sort, SortKind::available(&state.source));
+            });
+
+        if let Some(sort) = chosen.filter(|sort| Some(*sort) != current) {
+            state.set_sort(reddit, sort);
+        }
+        if let Some(sort) = chosen_search.filter(|sort| Some(*sort) != search) {
+            state.set_search_sort(reddit, sort);
+        }
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Sort
+    }
+
+    fn toggle_open(&mut self) {
+        self.window.open = !self.window.open
+    }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SearchMode {
+    Posts,
+    Subreddits,
+}
+
+/// Searches posts, shown as a feed, or subreddits, listed in the window.
+#[derive(Debug)]
+pub struct SearchWindow {
+    window: WindowState,
+    /// Whether a text field has focus, and thus disables keybinds.
+    has_focus: bool,
+    mode: SearchMode,
+    query: String,
+    /// Search one subreddit instead of all of them.
+    restrict: bool,
+    subreddit: String,
+    /// Kept for the next search.
+    sort: Sort<SearchSort>,
+    subreddit_query: String,
+}
+
+impl SearchWindow {
+    /// Reddit's search operators, and what they match.
+    const SYNTAX: [(&'static str, &'static str); 8] = [
+        ("author:name", "posts by a user"),
+        ("subreddit:name", "posts in a subreddit"),
+        ("title:text", "text in the title"),
+        ("selftext:text", "text in the body"),
+        ("site:example.com", "links to a domain"),
+        ("flair:text", "posts with a flair"),
+        ("self:yes", "text posts only"),
+        ("nsfw:no", "leave out NSFW posts"),
+    ];
+
+    fn new() -> Self {
+        Self {
+            window: WindowState::new(),
+            has_focus: false,
+            mode: SearchMode::Posts,
+            query: String::new(),
+            restrict: false,
+            subreddit: String::new(),
+            sort: Sort::default(),
+            subreddit_query: String::new(),
+        }
+    }
+
+    /// Returns whether a text field has focus.
+    fn posts(&mut self, ui: &mut egui::Ui, reddit: &Reddit, state: &mut State) -> bool {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.query)
+                .hint_text("Search posts")
+                .desired_width(f32::INFINITY),
+        );
+        let mut focused = response.has_focus();
+        let mut search = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+
+        if self.window.request_focus {
+            response.request_focus();
+            self.window.request_focus = false;
+        }
+
+        ui.collapsing("Query syntax", |ui| {
+            egui::Grid::new("search_syntax").show(ui, |ui| {
+                for (operator, meaning) in Self::SYNTAX {
+                    ui.monospace(operator);
+                    ui.label(meaning);
+                    ui.end_row();
+                }
+            });
+            ui.label("Combine terms with AND, OR and NOT, and group them with parentheses.");
+        });
+
+        ui.horizontal(|ui| {
+            ui.radio_value(&mut self.restrict, false, "Everywhere");
+            ui.radio_value(&mut self.restrict, true, "In /r/");
+
+            let response = ui.add_enabled(
+                self.restrict,
+                egui::TextEdit::singleline(&mut self.subreddit).hint_text("subreddit"),
+            );
+            focused |= response.has_focus();
+        });
+
+        ui.label("Sort by");
+        if let Some(sort) = pick_sort(ui, self.sort, &SearchSort::ALL) {
+            self.sort = sort;
+        }
+
+        let missing_subreddit = self.restrict && self.subreddit.trim().is_empty();
+        let complete = !self.query.trim().is_empty() && !missing_subreddit;
+
+        search |= ui
+            .add_enabled(complete, egui::Button::new("Search"))
+            .clicked();
+
+        if search && complete {
+            state.open_feed(reddit, self.source());
+        }
+
+        focused
+    }
+
+    fn source(&self) -> FeedSource {
+        let subreddit = self.subreddit.trim().trim_start_matches("r/");
+
+        FeedSource::Search {
+            query: self.query.trim().to_owned(),
+            subreddit: Some(subreddit.to_owned()).filter(|_| self.restrict),
+            sort: self.sort,
+        }
+    }
+
+    /// Returns whether a text field has focus.
+    fn subreddits(&mut self, ui: &mut egui::Ui, reddit: &Reddit, state: &mut State) -> bool {
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.subreddit_query)
+                .hint_text("Search subreddits")
+                .desired_width(f32::INFINITY),
+        );
+        let focused = response.has_focus();
+
+        if self.window.request_focus {
+            response.request_focus();
+            self.window.request_focus = false;
+        }
+
+        let query = self.subreddit_query.trim();
+        let search = response.lost_focus() && ui.input().key_pressed(egui::Key::Enter);
+
+        if (search || ui.button("Search").clicked()) && !query.is_empty() {
+            state.search_subreddits(query.to_owned());
+        }
+
+        ui.separator();
+
+        let mut chosen = None;
+
+        match state
+            .subreddit_search
+            .as_ref()
+            .map(|search| &search.results)
+        {
+            None => {}
+            Some(None) => {
+                ui.label("Searching..");
+            }
+            Some(Some(Err(err))) => {
+                ui.colored_label(egui::Color32::RED, err);
+            }
+            Some(Some(Ok(results))) if results.is_empty() => {
+                ui.label("No subreddits found");
+            }
+            Some(Some(Ok(results))) => {
+                egui::ScrollArea::vertical()
+                    .max_height(400f32)
+                    .show(ui, |ui| {
+                        for subreddit in results {
+                            if Self::ui_subreddit(ui, subreddit) {
+                                chosen = Some(subreddit.name.clone());
+                            }
+                            ui.separator();
+                        }
+                    });
+            }
+        }
+
+        if let Some(name) = chosen {
+            state.open_feed(reddit, FeedSource::Subreddit(name));
+        }
+
+        focused
+    }
+
+    /// Returns whether the subreddit was clicked.
+    fn ui_subreddit(ui: &mut egui::Ui, subreddit: &SubredditInfo) -> bool {
+        let mut clicked = false;
+
+        ui.horizontal(|ui| {
+            let name = egui::Label::new(format!("/r/{}", subreddit.name))
+                .text_color(ui.visuals().hyperlink_color)
+                .strong()
+                .sense(egui::Sense::click());
+            clicked = ui.add(name).on_hover_text("Open").clicked();
+
+            if subreddit.nsfw {
+                ui.colored_label(egui::Color32::RED, "NSFW");
+            }
+
+            if let Some(subscribers) = subreddit.subscribers {
+                ui.label(format!("{} subscribers", format_count(subscribers)));
+            }
+        });
+
+        if !subreddit.description.is_empty() {
+            ui.add(egui::Label::new(&subreddit.description).small().wrap(true));
+        }
+
+        clicked
+    }
+}
+
+impl Show for SearchWindow {
+    fn show(&mut self, ctx: &egui::CtxRef, reddit: &Reddit, state: &mut State) {
+        if !self.window.open {
+            self.window.request_focus = true;
+            set_claim(
+                &mut self.has_focus,
+                false,
+                &mut state.num_request_disable_binds,
+            );
+
+            // Searching within the shown subreddit is the likely next step.
+            if let FeedSource::Subreddit(name) = &state.source {
+                self.subreddit = name.clone();
+            }
+            return;
+        }
+
+        let mut open = self.window.open;
+
+        let focused = egui::Window::new("Search")
+            .open(&mut open)
+            .title_bar(state.options.show_title_bars)
+            .default_width(450f32)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    let mode = self.mode;
+                    ui.selectable_value(&mut self.mode, SearchMode::Posts, "Posts");
+                    ui.selectable_value(&mut self.mode, SearchMode::Subreddits, "Subreddits");
+
+                    if mode != self.mode {
+                        self.window.request_focus = true;
+                    }
+                });
+
+                ui.separator();
+
+                match self.mode {
+                    SearchMode::Posts => self.posts(ui, reddit, state),
+                    SearchMode::Subreddits => self.subreddits(ui, reddit, state),
+                }
+            })
+            .and_then(|response| response.inner)
+            .unwrap_or(false);
+
+        self.window.open = open;
+        set_claim(
+            &mut self.has_focus,
+            focused && open,
+            &mut state.num_request_disable_binds,
+        );
+    }
+
+    fn kind(&self) -> WindowKind {
+        WindowKind::Search
     }
 
     fn toggle_open(&mut self) {
         self.window.open = !self.window.open
     }
+
+    fn is_open(&self) -> bool {
+        self.window.open
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PostFeedComponent;
+
+    fn display(original: &str, max_width: f32) -> String {
+        PostFeedComponent::create_display_string(original, max_width, |_| 1f32)
+    }
+
+    #[test]
+    fn short_titles_are_untouched() {
+        for title in [
+            "Blåbærsyltetøy på brødskiva",
+            "日本語のタイトル",
+            "Ünïcödé 🦀 crab",
+            "Привет, мир",
+        ] {
+            assert_eq!(display(title, 100f32), title);
+        }
+    }
+
+    #[test]
+    fn truncates_by_width() {
+        assert_eq!(display("Æøå er norske bokstaver", 8f32), "Æøå e...");
+        assert_eq!(display("東京都の天気予報です", 7f32), "東京都の...");
+    }
+
+    #[test]
+    fn never_splits_graphemes() {
+        // "e" followed by a combining acute accent is a single grapheme of two chars.
+        assert_eq!(display("cafe\u{301} au lait", 8f32), "cafe\u{301}...");
+        assert_eq!(display("cafe\u{301} au lait", 7f32), "caf...");
+
+        // Family emoji, joined by zero width joiners.
+        let family = "👨\u{200d}👩\u{200d}👧";
+        let title = format!("{}{} is a family", family, family);
+        assert_eq!(display(&title, 8f32), format!("{}...", family));
+    }
+
+    #[test]
+    fn truncates_wide_glyphs() {
+        // CJK and emoji take two columns, like in the fonts they are rendered with.
+        let wide = |c: char| match c {
+            '\u{200d}' => 0f32,
+            c if c.is_ascii() => 1f32,
+            _ => 2f32,
+        };
+        let display_wide = |original, max_width| {
+            PostFeedComponent::create_display_string(original, max_width, wide)
+        };
+
+        assert_eq!(
+            display_wide("東京都の天気予報です", 20f32),
+            "東京都の天気予報です"
+        );
+        assert_eq!(display_wide("東京都の天気予報です", 9f32), "東京都...");
+        // A wide glyph that only half fits is dropped.
+        assert_eq!(display_wide("東京都の天気予報です", 8f32), "東京...");
+        assert_eq!(display_wide("🦀🦀🦀 crabs", 8f32), "🦀🦀...");
+    }
+
+    #[test]
+    fn uses_glyph_widths() {
+        let wide = |c: char| if c.is_ascii() { 1f32 } else { 2f32 };
+        assert_eq!(
+            PostFeedComponent::create_display_string("ab日本cd", 7f32, wide),
+            "ab日..."
+        );
+    }
 }
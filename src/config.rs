@@ -4,17 +4,21 @@ use serde_derive::Deserialize;
 
 use crate::{
     input::{KeyBind, KeyBinds},
-    Action,
+    Action, MessageAction,
 };
 
 #[derive(Debug)]
 pub struct Options {
     /// Keybinds that can perform som [`Action`]
     pub keybinds: KeyBinds,
+    /// Keybinds of the messages window, which replace the others while it is open
+    pub message_binds: HashMap<Key, MessageAction>,
     /// Whether the post is immediately rendered upon highlight, or if [`Action::OpenPost`] must be performed
     pub immediate_posts: bool,
     /// Whether title bars are rendered. Probably want this on until Esc closes current window.
     pub show_title_bars: bool,
+    /// Whether the feed continues where it was left last launch, or starts fresh at the top.
+    pub restore_session: bool,
     /// Number of posts to buffer. Max 50.
     pub buffer_amount: usize,
     /// The ratio of the buffer above and below the currently viewed post.
@@ -27,28 +31,28 @@ impl From<FileConfig> for Options {
         let mut keybinds = KeyBinds::default();
         for (key, details) in fc.binds.into_iter() {
             match details {
-                ConfigKey::Simple(action) => {
-                    keybinds.binds.insert(KeyBind::basic(key.into()), action)
-                }
+                ConfigKey::Simple(action) => keybinds.binds.insert(KeyBind::basic(key), action),
                 ConfigKey::Detailed(config) => {
                     let m = config.modifiers;
-                    let ctrl = m.iter().any(|m| *m == Mods::Ctrl);
-                    let shift = m.iter().any(|m| *m == Mods::Shift);
-                    let alt = m.iter().any(|m| *m == Mods::Alt);
+                    let ctrl = m.contains(&Mods::Ctrl);
+                    let shift = m.contains(&Mods::Shift);
+                    let alt = m.contains(&Mods::Alt);
 
                     keybinds
                         .binds
-                        .insert(KeyBind::new(key.into(), [ctrl, shift, alt]), config.action)
+                        .insert(KeyBind::new(key, [ctrl, shift, alt]), config.action)
                 }
             };
         }
 
         Self {
             keybinds,
+            message_binds: fc.message_binds.unwrap_or_else(default_message_binds),
             immediate_posts: fc.immediate_posts.unwrap_or(false),
             show_title_bars: fc.show_title_bars.unwrap_or(true),
-            buffer_amount: fc.buffer_amount.unwrap_or(25).min(50).max(1),
-            buffer_ratio: fc.buffer_ratio.unwrap_or(0.75).min(1f32).max(0f32),
+            restore_session: fc.restore_session.unwrap_or(true),
+            buffer_amount: fc.buffer_amount.unwrap_or(25).clamp(1, 50),
+            buffer_ratio: fc.buffer_ratio.unwrap_or(0.75).clamp(0f32, 1f32),
         }
     }
 }
@@ -68,8 +72,10 @@ impl Default for Options {
 #[derive(Debug, Deserialize)]
 pub struct FileConfig {
     pub binds: HashMap<Key, ConfigKey>,
+    pub message_binds: Option<HashMap<Key, MessageAction>>,
     pub immediate_posts: Option<bool>,
     pub show_title_bars: Option<bool>,
+    pub restore_session: Option<bool>,
     pub buffer_amount: Option<usize>,
     pub buffer_ratio: Option<f32>,
 }
@@ -102,6 +108,18 @@ fn no_mods() -> Vec<Mods> {
     vec![]
 }
 
+fn default_message_binds() -> HashMap<Key, MessageAction> {
+    HashMap::from([
+        (Key::K, MessageAction::ConversationUp),
+        (Key::ArrowUp, MessageAction::ConversationUp),
+        (Key::J, MessageAction::ConversationDown),
+        (Key::ArrowDown, MessageAction::ConversationDown),
+        (Key::R, MessageAction::Reply),
+        (Key::N, MessageAction::Compose),
+        (Key::Escape, MessageAction::Close),
+    ])
+}
+
 #[derive(Debug, Copy, Clone, Hash, Eq, PartialEq, Deserialize)]
 #[serde(try_from = "String")]
 pub enum Key {
@@ -281,7 +299,8 @@ impl TryFrom<String> for Key {
             "home" => Ok(Self::Home),
             "end" => Ok(Self::End),
             "pageup" => Ok(Self::PageUp),
-            "pagedown" => Ok(Self::Insert),
+            "pagedown" => Ok(Self::PageDown),
+            "delete" => Ok(Self::Delete),
             _ => Err(NoMatchingKey(value)),
         }
     }
@@ -0,0 +1,318 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use snew::{
+    reddit::{Reddit, TimePeriod},
+    things::PostFeed,
+};
+
+/// How far back top and controversial sorts look.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimeRange {
+    Hour,
+    Day,
+    Week,
+    Month,
+    Year,
+    All,
+}
+
+impl TimeRange {
+    pub const ALL: [TimeRange; 6] = [
+        TimeRange::Hour,
+        TimeRange::Day,
+        TimeRange::Week,
+        TimeRange::Month,
+        TimeRange::Year,
+        TimeRange::All,
+    ];
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL
+            .iter()
+            .position(|range| range == self)
+            .unwrap_or(0);
+
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TimeRange::Hour => "hour",
+            TimeRange::Day => "day",
+            TimeRange::Week => "week",
+            TimeRange::Month => "month",
+            TimeRange::Year => "year",
+            TimeRange::All => "all time",
+        }
+    }
+
+    fn period(&self) -> TimePeriod {
+        match self {
+            TimeRange::Hour => TimePeriod::Hour,
+            TimeRange::Day => TimePeriod::Day,
+            TimeRange::Week => TimePeriod::Week,
+            TimeRange::Month => TimePeriod::Month,
+            TimeRange::Year => TimePeriod::Year,
+            TimeRange::All => TimePeriod::All,
+        }
+    }
+}
+
+/// How the posts of a feed can be ordered, shared by listing and search sorts.
+pub trait SortOrder: Copy + PartialEq {
+    fn name(&self) -> &'static str;
+
+    /// Whether the time range applies.
+    fn has_range(&self) -> bool;
+}
+
+/// Orders of the frontpage and subreddit listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortKind {
+    Best,
+    Hot,
+    New,
+    Top,
+    Rising,
+    Controversial,
+}
+
+impl SortKind {
+    /// Best is personalised, so only the frontpage has it.
+    pub fn available(source: &FeedSource) -> &'static [SortKind] {
+        match source {
+            FeedSource::Frontpage => &[
+                SortKind::Best,
+                SortKind::Hot,
+                SortKind::New,
+                SortKind::Top,
+                SortKind::Rising,
+                SortKind::Controversial,
+            ],
+            _ => &[
+                SortKind::Hot,
+                SortKind::New,
+                SortKind::Top,
+                SortKind::Rising,
+                SortKind::Controversial,
+            ],
+        }
+    }
+
+    pub fn next(&self, source: &FeedSource) -> Self {
+        let kinds = Self::available(source);
+        let idx = kinds.iter().position(|kind| kind == self).unwrap_or(0);
+
+        kinds[(idx + 1) % kinds.len()]
+    }
+}
+
+impl SortOrder for SortKind {
+    fn name(&self) -> &'static str {
+        match self {
+            SortKind::Best => "best",
+            SortKind::Hot => "hot",
+            SortKind::New => "new",
+            SortKind::Top => "top",
+            SortKind::Rising => "rising",
+            SortKind::Controversial => "controversial",
+        }
+    }
+
+    fn has_range(&self) -> bool {
+        matches!(self, SortKind::Top | SortKind::Controversial)
+    }
+}
+
+/// Orders of search results, which differ from those of listings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchSort {
+    Relevance,
+    Hot,
+    Top,
+    New,
+    Comments,
+}
+
+impl SearchSort {
+    pub const ALL: [SearchSort; 5] = [
+        SearchSort::Relevance,
+        SearchSort::Hot,
+        SearchSort::Top,
+        SearchSort::New,
+        SearchSort::Comments,
+    ];
+
+    pub fn next(&self) -> Self {
+        let idx = Self::ALL.iter().position(|kind| kind == self).unwrap_or(0);
+
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+impl SortOrder for SearchSort {
+    fn name(&self) -> &'static str {
+        match self {
+            SearchSort::Relevance => "relevance",
+            SearchSort::Hot => "hot",
+            SearchSort::Top => "top",
+            SearchSort::New => "new",
+            SearchSort::Comments => "comments",
+        }
+    }
+
+    fn has_range(&self) -> bool {
+        matches!(
+            self,
+            SearchSort::Relevance | SearchSort::Top | SearchSort::Comments
+        )
+    }
+}
+
+/// Order of the posts in a feed. The range is kept when switching to a sort without one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Sort<K = SortKind> {
+    pub kind: K,
+    pub range: TimeRange,
+}
+
+impl Default for Sort {
+    fn default() -> Self {
+        Self {
+            kind: SortKind::Hot,
+            range: TimeRange::Day,
+        }
+    }
+}
+
+impl Default for Sort<SearchSort> {
+    fn default() -> Self {
+        Self {
+            kind: SearchSort::Relevance,
+            range: TimeRange::All,
+        }
+    }
+}
+
+impl<K: SortOrder> fmt::Display for Sort<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.kind.has_range() {
+            write!(f, "{} ({})", self.kind.name(), self.range.name())
+        } else {
+            write!(f, "{}", self.kind.name())
+        }
+    }
+}
+
+/// Where a feed was left, so the next launch can continue there.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    /// Listing cursor for the posts after the saved ones.
+    pub after: Option<String>,
+    /// Fullnames of the loaded posts, in order.
+    pub posts: Vec<String>,
+    /// Fullname of the highlighted post.
+    pub highlighted: Option<String>,
+}
+
+impl Position {
+    /// Reddit looks up at most this many posts at once.
+    pub const MAX_POSTS: usize = 100;
+}
+
+/// Where the posts of the feed come from.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FeedSource {
+    #[default]
+    Frontpage,
+    Subreddit(String),
+    /// The history of a user, see [`crate::profile::Profile`].
+    User(String),
+    /// Posts matching a query, in one subreddit or all of them.
+    Search {
+        query: String,
+        subreddit: Option<String>,
+        #[serde(default)]
+        sort: Sort<SearchSort>,
+    },
+}
+
+impl fmt::Display for FeedSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FeedSource::Frontpage => write!(f, "Frontpage"),
+            FeedSource::Subreddit(name) => write!(f, "/r/{}", name),
+            FeedSource::User(name) => write!(f, "/u/{}", name),
+            FeedSource::Search {
+                query,
+                subreddit: Some(subreddit),
+                ..
+            } => write!(f, "\"{}\" in /r/{}", query, subreddit),
+            FeedSource::Search { query, .. } => write!(f, "\"{}\"", query),
+        }
+    }
+}
+
+impl FeedSource {
+    /// What the sort of this listing is remembered under, `None` if it isn't a listing.
+    /// Searches carry their sort instead.
+    pub fn sort_key(&self) -> Option<String> {
+        match self {
+            FeedSource::Frontpage => Some(String::new()),
+            FeedSource::Subreddit(name) => Some(name.to_lowercase()),
+            FeedSource::User(_) | FeedSource::Search { .. } => None,
+        }
+    }
+
+    /// The sort of a search, `None` for other sources.
+    pub fn search_sort(&self) -> Option<Sort<SearchSort>> {
+        match self {
+            FeedSource::Search { sort, .. } => Some(*sort),
+            _ => None,
+        }
+    }
+
+    /// `None` for sources that have feeds of their own, like profiles.
+    /// Listings are sorted by `sort`, searches by their own.
+    pub fn feed(&self, reddit: &Reddit, sort: Sort) -> Option<PostFeed> {
+        let range = sort.range.period();
+
+        let feed = match self {
+            FeedSource::Frontpage => {
+                let frontpage = reddit.frontpage();
+                match sort.kind {
+                    SortKind::Best => frontpage.best(),
+                    SortKind::Hot => frontpage.hot(),
+                    SortKind::New => frontpage.new(),
+                    SortKind::Top => frontpage.top(range),
+                    SortKind::Rising => frontpage.rising(),
+                    SortKind::Controversial => frontpage.controversial(range),
+                }
+            }
+            FeedSource::Subreddit(name) => {
+                let subreddit = reddit.subreddit(name);
+                match sort.kind {
+                    SortKind::Best | SortKind::Hot => subreddit.hot(),
+                    SortKind::New => subreddit.new(),
+                    SortKind::Top => subreddit.top(range),
+                    SortKind::Rising => subreddit.rising(),
+                    SortKind::Controversial => subreddit.controversial(range),
+                }
+            }
+            FeedSource::User(_) => return None,
+            FeedSource::Search {
+                query,
+                subreddit: Some(subreddit),
+                sort,
+            } => reddit
+                .subreddit(subreddit)
+                .search(query, sort.kind.name(), sort.range.period()),
+            FeedSource::Search { query, sort, .. } => {
+                reddit.search(query, sort.kind.name(), sort.range.period())
+            }
+        };
+
+        Some(feed)
+    }
+}
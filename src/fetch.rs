@@ -1,22 +1,73 @@
 use bytes::Bytes;
 use crossbeam_channel::{unbounded, Receiver, Sender};
-use eframe::egui;
+use eframe::{egui, epi::RepaintSignal};
 use snew::{
-    auth::UserAuthenticator,
+    auth::{CodeFlow, UserAuthenticator},
     content::Content,
     reddit::Reddit,
-    things::{Post, PostFeed},
+    things::{CommentFeed, Me, More, Post, PostFeed, Reply},
+};
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    thread,
+    time::Duration,
 };
-use std::{sync::Arc, thread, time::Duration};
 
-use crate::{components::PostId, state::State, SnuiApp};
+use crate::{
+    accounts::LOGIN_TIMEOUT,
+    components::{
+        CommentsKey, Flair, OutgoingMessage, PostId, PostKind, ReplyTarget, Submission,
+        SubmitError, SubredditInfo, Subscription, VoteRequest,
+    },
+    feed::Position,
+    inbox::InboxItem,
+    profile::{About, Profile, UserComment},
+    state::State,
+    util, SnuiApp,
+};
 // todo: make this module a bit less.. manual
 
 pub enum Message {
     PostsReady(Vec<Post>, PostFeed),
     ContentReady(Content, PostId),
-    ImageDecoded(Vec<egui::Color32>, (usize, usize), PostId),
-    UserLoggedIn(UserAuthenticator),
+    /// `None` if the image could not be decoded.
+    ImageDecoded(Option<(Vec<egui::Color32>, (usize, usize))>, PostId),
+    /// The authorization url of the login, and its outcome.
+    UserLoggedIn(String, Result<UserAuthenticator, String>),
+    /// Who a client is logged in as. The account name is `None` for a new login.
+    MeReady(Option<String>, Reddit, Result<Me, String>),
+    PollVoted(PostId, Result<(), String>),
+    CommentsReady(CommentsKey, Result<Vec<Reply>, String>),
+    /// The replies a "load more" stub stood for, and the parent of the stub.
+    MoreCommentsReady(CommentsKey, String, Result<Vec<Reply>, String>),
+    ReplySubmitted(ReplyTarget, Result<Reply, String>),
+    Voted(VoteRequest, Result<(), String>),
+    SavedChanged(String, bool, Result<(), String>),
+    HiddenChanged(String, bool, Result<(), String>),
+    SubscriptionsReady(Result<Vec<Subscription>, String>),
+    SubscribedChanged(String, bool, Result<(), String>),
+    SubredditsFound(String, Result<Vec<SubredditInfo>, String>),
+    /// Names of subreddits reddit completes a query to.
+    SubredditsAutocompleted(String, Result<Vec<String>, String>),
+    /// The first posts of a subreddit, or why it can't be shown.
+    SubredditOpened(String, Result<(Vec<Post>, PostFeed), String>),
+    /// `None` if the icon could not be downloaded or decoded.
+    IconDecoded(String, Option<(Vec<egui::Color32>, (usize, usize))>),
+    LinkFlairsReady(String, Result<Vec<Flair>, String>),
+    PostSubmitted(Result<Post, Vec<SubmitError>>),
+    InboxReady(Result<Vec<InboxItem>, String>),
+    MarkedRead(Vec<String>, Result<(), String>),
+    SentMessagesReady(Result<Vec<InboxItem>, String>),
+    PrivateMessageSent(OutgoingMessage, Result<(), String>),
+    TokenRevoked(Result<(), String>),
+    /// Posts a feed was left with last launch, and the post to highlight.
+    PositionRestored(PostFeed, Option<String>, Result<Vec<Post>, String>),
+    UserAboutReady(String, Result<About, String>),
+    /// The next comments of a user, and the feed to fetch more from.
+    UserCommentsReady(String, CommentFeed, Result<Vec<UserComment>, String>),
 }
 
 pub trait Fetch {
@@ -42,6 +93,12 @@ impl Fetch for MorePosts {
     }
 }
 
+/// Runs requests on other threads, and hands their results back as [`Message`]s.
+///
+/// Results for the shown feed are sent on a channel that [`Self::reset`] replaces, so
+/// a new feed never receives them. Results that matter regardless of the feed, like
+/// the login, the logged in user and token revocation, are sent on a lasting channel
+/// that survives resets.
 #[derive(Debug)]
 pub struct Fetcher {
     /// Receiver of messages created on other threads
@@ -49,16 +106,24 @@ pub struct Fetcher {
     /// Sender for giving out
     pub sender: Sender<Message>,
     num_senders: u32,
+    /// Receiver of messages that survive [`Self::reset`]
+    lasting_receiver: Receiver<Message>,
+    lasting_sender: Sender<Message>,
+    num_lasting_senders: u32,
 }
 
 impl Default for Fetcher {
     fn default() -> Self {
         let (sender, receiver) = unbounded();
+        let (lasting_sender, lasting_receiver) = unbounded();
 
         Self {
             receiver,
             sender,
             num_senders: Default::default(),
+            lasting_receiver,
+            lasting_sender,
+            num_lasting_senders: 0,
         }
     }
 }
@@ -70,22 +135,24 @@ impl Fetcher {
                 self.num_senders -= 1;
                 Some(msg)
             }
-            Err(_) => None,
+            Err(_) => {
+                let msg = self.lasting_receiver.try_recv().ok()?;
+                self.num_lasting_senders -= 1;
+                Some(msg)
+            }
         }
     }
 
     pub fn reset(&mut self) {
         let (sender, receiver) = unbounded();
 
-        *self = Self {
-            receiver,
-            sender,
-            num_senders: 0,
-        }
+        self.receiver = receiver;
+        self.sender = sender;
+        self.num_senders = 0;
     }
 
     pub fn is_working(&self) -> bool {
-        self.num_senders > 0
+        self.num_senders > 0 || self.num_lasting_senders > 0
     }
 
     pub fn get<T: Fetch>(&mut self, reddit: Reddit, state: &mut State) {
@@ -104,46 +171,559 @@ impl Fetcher {
         });
     }
 
-    pub fn decode_image(&mut self, image: Bytes, post_id: PostId) {
+    pub fn get_comments(&mut self, post: Arc<Post>, key: CommentsKey) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let comments = post
+                .comments(key.sort.as_str())
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::CommentsReady(key, comments));
+        });
+    }
+
+    /// Fetch the comments a "load more" or "continue this thread" stub stands for.
+    pub fn get_more_comments(&mut self, post: Arc<Post>, key: CommentsKey, more: More) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let replies = if more.count == 0 {
+                // "continue this thread" has no children to ask for, so fetch the parent's thread.
+                let parent = more.parent_id.trim_start_matches("t1_");
+
+                post.comment_thread(parent, key.sort.as_str())
+                    .map(|thread| match thread.into_iter().next() {
+                        Some(Reply::Comment(parent)) => parent.replies,
+                        _ => vec![],
+                    })
+            } else {
+                post.more_comments(&more, key.sort.as_str())
+            };
+
+            let replies = replies.map_err(|err| err.to_string());
+            let _ = s.send(Message::MoreCommentsReady(key, more.parent_id, replies));
+        });
+    }
+
+    pub fn submit_reply(&mut self, reddit: Reddit, target: ReplyTarget, text: String) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let reply = reddit
+                .comment(&target.name, &text)
+                .map(Reply::Comment)
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::ReplySubmitted(target, reply));
+        });
+    }
+
+    pub fn vote(&mut self, reddit: Reddit, request: VoteRequest) {
         let s = self.sender.clone();
         self.num_senders += 1;
+
         thread::spawn(move || {
-            let image = image::load_from_memory(&image).unwrap();
-            let image = image.to_rgba8();
+            let result = reddit
+                .vote(&request.name, request.vote.value())
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::Voted(request, result));
+        });
+    }
+
+    pub fn set_saved(&mut self, reddit: Reddit, name: String, saved: bool) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let result = if saved {
+                reddit.save(&name)
+            } else {
+                reddit.unsave(&name)
+            };
 
-            let size = (image.width() as usize, image.height() as usize);
+            let result = result.map_err(|err| err.to_string());
+            let _ = s.send(Message::SavedChanged(name, saved, result));
+        });
+    }
+
+    pub fn set_hidden(&mut self, reddit: Reddit, name: String, hidden: bool) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let result = if hidden {
+                reddit.hide(&name)
+            } else {
+                reddit.unhide(&name)
+            };
+
+            let result = result.map_err(|err| err.to_string());
+            let _ = s.send(Message::HiddenChanged(name, hidden, result));
+        });
+    }
+
+    pub fn get_subscriptions(&mut self, reddit: Reddit) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
 
-            let image = image
-                .chunks(4)
-                .map(|pixel| {
-                    egui::Color32::from_rgba_unmultiplied(pixel[0], pixel[1], pixel[2], pixel[3])
+        thread::spawn(move || {
+            let subscriptions = reddit
+                .subscriptions()
+                .map(|subreddits| {
+                    subreddits
+                        .into_iter()
+                        .map(|subreddit| Subscription {
+                            name: subreddit.display_name,
+                            icon_url: subreddit.icon_img.filter(|url| !url.is_empty()),
+                        })
+                        .collect()
                 })
-                .collect::<Vec<egui::Color32>>();
+                .map_err(|err| err.to_string());
 
-            let _ = s.send(Message::ImageDecoded(image, size, post_id));
+            let _ = s.send(Message::SubscriptionsReady(subscriptions));
         });
     }
 
-    pub fn start_login_process(&mut self) {
+    pub fn search_subreddits(&mut self, reddit: Reddit, query: String) {
         let s = self.sender.clone();
         self.num_senders += 1;
 
         thread::spawn(move || {
-            let auth = Reddit::perform_code_flow(
-                SnuiApp::CLIENT_ID,
-                "Success. You can now return to SnUI.",
-                Some(Duration::from_secs(240)),
-            );
+            let subreddits = reddit
+                .search_subreddits(&query)
+                .map(|subreddits| {
+                    subreddits
+                        .into_iter()
+                        .map(|subreddit| SubredditInfo {
+                            name: subreddit.display_name,
+                            subscribers: subreddit.subscribers,
+                            description: subreddit.public_description,
+                            nsfw: subreddit.over18,
+                        })
+                        .collect()
+                })
+                .map_err(|err| err.to_string());
 
-            match auth {
-                Ok(auth) => {
-                    let _ = s.send(Message::UserLoggedIn(auth));
-                }
+            let _ = s.send(Message::SubredditsFound(query, subreddits));
+        });
+    }
+
+    pub fn autocomplete_subreddits(&mut self, reddit: Reddit, query: String) {
+        let s = self.lasting_sender.clone();
+        self.num_lasting_senders += 1;
+
+        thread::spawn(move || {
+            let names = reddit
+                .subreddit_autocomplete(&query)
+                .map(|subreddits| {
+                    subreddits
+                        .into_iter()
+                        .map(|subreddit| subreddit.display_name)
+                        .collect()
+                })
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::SubredditsAutocompleted(query, names));
+        });
+    }
+
+    /// Fetch the first posts of a subreddit, failing if there are none to show.
+    pub fn open_subreddit(&mut self, mut feed: PostFeed, name: String) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
 
-                Err(err) => {
-                    println!("ERROR {}", err)
+        thread::spawn(move || {
+            // Reddit answers for missing, banned and private subreddits with an error or nothing.
+            let result = match feed.next() {
+                None => Err(format!("/r/{} doesn't exist, or has no posts", name)),
+                Some(Err(err)) => Err(format!("/r/{} can't be opened: {}", name, err)),
+                Some(Ok(first)) => {
+                    let mut posts = vec![first];
+                    posts.extend(feed.by_ref().filter_map(|p| p.ok()).take(14));
+                    Ok((posts, feed))
                 }
             };
+
+            let _ = s.send(Message::SubredditOpened(name, result));
+        });
+    }
+
+    pub fn set_subscribed(&mut self, reddit: Reddit, subreddit: String, subscribed: bool) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let result = if subscribed {
+                reddit.subscribe(&subreddit)
+            } else {
+                reddit.unsubscribe(&subreddit)
+            };
+
+            let result = result.map_err(|err| err.to_string());
+            let _ = s.send(Message::SubscribedChanged(subreddit, subscribed, result));
+        });
+    }
+
+    pub fn get_link_flairs(&mut self, reddit: Reddit, subreddit: String) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let flairs = reddit
+                .subreddit(&subreddit)
+                .link_flairs()
+                .map(|flairs| {
+                    flairs
+                        .into_iter()
+                        .map(|flair| Flair {
+                            id: flair.id,
+                            text: flair.text,
+                        })
+                        .collect()
+                })
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::LinkFlairsReady(subreddit, flairs));
+        });
+    }
+
+    /// Submit a post, and fetch it once reddit accepts it.
+    pub fn submit_post(&mut self, reddit: Reddit, submission: Submission) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let _ = s.send(Message::PostSubmitted(Self::submit(&reddit, submission)));
+        });
+    }
+
+    fn submit(reddit: &Reddit, submission: Submission) -> Result<Post, Vec<SubmitError>> {
+        let general = |err: snew::reddit::Error| {
+            vec![SubmitError {
+                field: None,
+                message: err.to_string(),
+            }]
+        };
+
+        let (kind, content) = match submission.kind {
+            PostKind::Text => ("self", submission.content),
+            PostKind::Link => ("link", submission.content),
+            // Images are uploaded first, and submitted by the url reddit hosts them at.
+            PostKind::Image => (
+                "image",
+                reddit.upload_media(&submission.content).map_err(general)?,
+            ),
+        };
+        let content_key = if kind == "self" { "text" } else { "url" };
+        let flair_id = submission.flair_id.unwrap_or_default();
+
+        let form = [
+            ("sr", submission.subreddit.as_str()),
+            ("title", submission.title.as_str()),
+            ("kind", kind),
+            (content_key, content.as_str()),
+            ("flair_id", flair_id.as_str()),
+            ("nsfw", if submission.nsfw { "true" } else { "false" }),
+            ("spoiler", if submission.spoiler { "true" } else { "false" }),
+        ];
+
+        let response = reddit.submit(&form).map_err(general)?;
+
+        if !response.errors.is_empty() {
+            return Err(response
+                .errors
+                .into_iter()
+                .map(|(_code, message, field)| SubmitError {
+                    field: (!field.is_empty()).then_some(field),
+                    message,
+                })
+                .collect());
+        }
+
+        let name = response.name.ok_or_else(|| {
+            vec![SubmitError {
+                field: None,
+                message: "Reddit did not return the new post".to_owned(),
+            }]
+        })?;
+
+        reddit.post(&name).map_err(general)
+    }
+
+    pub fn get_inbox(&mut self, reddit: Reddit) {
+        let s = self.lasting_sender.clone();
+        self.num_lasting_senders += 1;
+
+        thread::spawn(move || {
+            let inbox = reddit
+                .inbox()
+                .map(|messages| messages.into_iter().map(InboxItem::new).collect())
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::InboxReady(inbox));
+        });
+    }
+
+    pub fn get_user_about(&mut self, reddit: Reddit, name: String) {
+        let s = self.lasting_sender.clone();
+        self.num_lasting_senders += 1;
+
+        thread::spawn(move || {
+            let about = reddit
+                .user(&name)
+                .about()
+                .map(|about| About {
+                    link_karma: about.link_karma,
+                    comment_karma: about.comment_karma,
+                    created_utc: about.created_utc,
+                })
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::UserAboutReady(name, about));
+        });
+    }
+
+    /// Fetch the next comments of a user, newest first.
+    pub fn get_user_comments(&mut self, name: String, mut feed: CommentFeed) {
+        let s = self.lasting_sender.clone();
+        self.num_lasting_senders += 1;
+
+        thread::spawn(move || {
+            let comments = feed
+                .by_ref()
+                .take(Profile::COMMENT_PAGE)
+                .map(|comment| comment.map(UserComment::from))
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::UserCommentsReady(name, feed, comments));
+        });
+    }
+
+    /// Fetch the private messages the user sent.
+    pub fn get_sent_messages(&mut self, reddit: Reddit) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let sent = reddit
+                .sent_messages()
+                .map(|messages| messages.into_iter().map(InboxItem::new).collect())
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::SentMessagesReady(sent));
+        });
+    }
+
+    /// Send a private message, either as a new conversation or a reply in one.
+    pub fn send_message(&mut self, reddit: Reddit, message: OutgoingMessage) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let result = match &message.reply_to {
+                Some((parent, _)) => reddit.comment(parent, &message.text).map(|_| ()),
+                None => reddit.compose(&message.to, &message.subject, &message.text),
+            };
+
+            let result = result.map_err(|err| err.to_string());
+            let _ = s.send(Message::PrivateMessageSent(message, result));
+        });
+    }
+
+    /// Mark inbox items, by fullname, as read.
+    pub fn mark_read(&mut self, reddit: Reddit, names: Vec<String>) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let result = reddit
+                .mark_read(&names.join(","))
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::MarkedRead(names, result));
+        });
+    }
+
+    /// Wake the app up after `delay`, even if the user is idle. Nothing is sent.
+    pub fn repaint_after(&self, signal: Arc<dyn RepaintSignal>, delay: Duration) {
+        thread::spawn(move || {
+            thread::sleep(delay);
+            signal.request_repaint();
+        });
+    }
+
+    /// Fetch and decode the icon of a subreddit. Icons that fail to load are left out.
+    pub fn get_icon(&mut self, subreddit: String, url: String) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let icon = reqwest::blocking::get(&url)
+                .and_then(|response| response.bytes())
+                .ok()
+                .and_then(|bytes| decode(&bytes));
+
+            let _ = s.send(Message::IconDecoded(subreddit, icon));
+        });
+    }
+
+    pub fn decode_image(&mut self, image: Bytes, post_id: PostId) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+        thread::spawn(move || {
+            let _ = s.send(Message::ImageDecoded(decode(&image), post_id));
+        });
+    }
+
+    pub fn vote_poll(&mut self, post: Arc<Post>, option: String, post_id: PostId) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let result = post.vote_poll(&option).map_err(|err| err.to_string());
+
+            let _ = s.send(Message::PollVoted(post_id, result));
+        });
+    }
+
+    /// Fetch the posts a feed was left with, to continue where it was.
+    pub fn restore_position(&mut self, reddit: Reddit, feed: PostFeed, position: Position) {
+        let s = self.sender.clone();
+        self.num_senders += 1;
+
+        thread::spawn(move || {
+            let posts = reddit.info(&position.posts).map_err(|err| err.to_string());
+
+            let _ = s.send(Message::PositionRestored(feed, position.highlighted, posts));
+        });
+    }
+
+    /// Tell reddit a refresh token will not be used again.
+    pub fn revoke_token(&mut self, refresh_token: String) {
+        let s = self.lasting_sender.clone();
+        self.num_lasting_senders += 1;
+
+        thread::spawn(move || {
+            let result = reqwest::blocking::Client::new()
+                .post("https://www.reddit.com/api/v1/revoke_token")
+                .header(reqwest::header::USER_AGENT, "snui")
+                .basic_auth(SnuiApp::CLIENT_ID, Some(""))
+                .form(&[
+                    ("token", refresh_token.as_str()),
+                    ("token_type_hint", "refresh_token"),
+                ])
+                .send()
+                .and_then(|response| response.error_for_status())
+                .map(|_| ())
+                .map_err(|err| err.to_string());
+
+            let _ = s.send(Message::TokenRevoked(result));
+        });
+    }
+
+    /// Start listening for the redirect from reddit. Returns the url the user should authorize SnUI at,
+    /// and what stops the listening.
+    pub fn start_login_process(&mut self) -> Result<(String, LoginCancel), String> {
+        let flow = CodeFlow::new(SnuiApp::CLIENT_ID, "Success. You can now return to SnUI.")
+            .map_err(|err| err.to_string())?;
+        let url = flow.url().to_owned();
+        let cancel = LoginCancel::new(&url);
+
+        let s = self.lasting_sender.clone();
+        self.num_lasting_senders += 1;
+
+        let login = url.clone();
+        let cancelled = cancel.cancelled.clone();
+        thread::spawn(move || {
+            let auth = flow
+                .wait(Some(LOGIN_TIMEOUT))
+                .map_err(|err| err.to_string());
+
+            // A user who authorized just as the login was cancelled is not logged in.
+            let auth = if cancelled.load(Ordering::SeqCst) {
+                Err("Cancelled".to_owned())
+            } else {
+                auth
+            };
+
+            let _ = s.send(Message::UserLoggedIn(login, auth));
+        });
+
+        Ok((url, cancel))
+    }
+
+    /// Find out who a client is logged in as.
+    pub fn get_me(&mut self, reddit: Reddit, account: Option<String>) {
+        let s = self.lasting_sender.clone();
+        self.num_lasting_senders += 1;
+
+        thread::spawn(move || {
+            let me = reddit.me().map_err(|err| err.to_string());
+
+            let _ = s.send(Message::MeReady(account, reddit, me));
         });
     }
 }
+
+/// Stops a login from waiting for the redirect from reddit, which frees the port it listens on.
+#[derive(Debug)]
+pub struct LoginCancel {
+    cancelled: Arc<AtomicBool>,
+    /// The redirect reddit makes when the user declines, which ends the wait.
+    declined: Option<String>,
+}
+
+impl LoginCancel {
+    fn new(url: &str) -> Self {
+        let declined = match (
+            util::query_param(url, "redirect_uri"),
+            util::query_param(url, "state"),
+        ) {
+            (Some(redirect), Some(state)) => {
+                Some(format!("{}?state={}&error=access_denied", redirect, state))
+            }
+            _ => None,
+        };
+
+        Self {
+            cancelled: Arc::new(AtomicBool::new(false)),
+            declined,
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+
+        // Waited on, so a login started next can listen on the same port. The listener is local, so
+        // this is quick.
+        if let Some(declined) = &self.declined {
+            let client = reqwest::blocking::Client::builder()
+                .timeout(Duration::from_secs(1))
+                .build();
+
+            if let Ok(client) = client {
+                let _ = client.get(declined).send();
+            }
+        }
+    }
+}
+
+fn decode(bytes: &[u8]) -> Option<(Vec<egui::Color32>, (usize, usize))> {
+    let image = image::load_from_memory(bytes).ok()?;
+    let image = image.to_rgba8();
+
+    let size = (image.width() as usize, image.height() as usize);
+
+    let image = image
+        .chunks(4)
+        .map(|pixel| egui::Color32::from_rgba_unmultiplied(pixel[0], pixel[1], pixel[2], pixel[3]))
+        .collect::<Vec<egui::Color32>>();
+
+    Some((image, size))
+}
@@ -0,0 +1,56 @@
+use std::{borrow::Cow, fs, path::Path};
+
+use eframe::egui::{FontDefinitions, FontFamily};
+
+/// Fallback fonts shipped in `fonts/`, in priority order. These are tried after egui's own fonts,
+/// so latin text keeps its look.
+const BUNDLED_FONTS: [(&str, &[u8]); 4] = [
+    (
+        "NotoSans-Regular.ttf",
+        include_bytes!("../fonts/NotoSans-Regular.ttf"),
+    ),
+    ("DejaVuSans.ttf", include_bytes!("../fonts/DejaVuSans.ttf")),
+    // The 2350 hangul syllables of KS X 1001, which covers everyday korean.
+    (
+        "NotoSansKR-Regular-Hangul.otf",
+        include_bytes!("../fonts/NotoSansKR-Regular-Hangul.otf"),
+    ),
+    // Same name as egui's copy, so emoji do not depend on its default fonts.
+    (
+        "NotoEmoji-Regular",
+        include_bytes!("../fonts/NotoEmoji-Regular.ttf"),
+    ),
+];
+
+/// Directory a CJK font is loaded from, relative to the working directory like `config.toml`.
+const FONT_DIR: &str = "./fonts";
+
+/// Han and kana are not bundled, so a full CJK font is used if it is put in [`FONT_DIR`]. Tried last.
+const CJK_FONT: &str = "NotoSansCJK-Regular.ttc";
+
+/// egui's default font definitions, with the fallback fonts appended to both families.
+pub fn definitions() -> FontDefinitions {
+    let mut definitions = FontDefinitions::default();
+
+    for (name, data) in BUNDLED_FONTS {
+        add_fallback(&mut definitions, name, Cow::Borrowed(data));
+    }
+
+    if let Ok(data) = fs::read(Path::new(FONT_DIR).join(CJK_FONT)) {
+        add_fallback(&mut definitions, CJK_FONT, Cow::Owned(data));
+    }
+
+    definitions
+}
+
+fn add_fallback(definitions: &mut FontDefinitions, name: &str, data: Cow<'static, [u8]>) {
+    definitions.font_data.insert(name.to_owned(), data);
+
+    for family in [FontFamily::Proportional, FontFamily::Monospace] {
+        if let Some(fonts) = definitions.fonts_for_family.get_mut(&family) {
+            if !fonts.iter().any(|font| font == name) {
+                fonts.push(name.to_owned());
+            }
+        }
+    }
+}
@@ -0,0 +1,71 @@
+use snew::things::PostFeed;
+
+use crate::{
+    components::{PostId, ViewablePost},
+    feed::{FeedSource, Sort},
+    profile::Profile,
+};
+
+/// Entries kept in each direction. Older ones are forgotten.
+const MAX_ENTRIES: usize = 20;
+
+/// A feed that was navigated away from, kept so it can be returned to without fetching it again.
+#[derive(Debug)]
+pub struct Entry {
+    pub source: FeedSource,
+    pub sort: Option<Sort>,
+    /// `None` if the feed was loading when left. A new cursor continues after the posts then.
+    pub feed: Option<PostFeed>,
+    pub posts: Vec<ViewablePost>,
+    pub profile: Option<Profile>,
+    pub highlighted: PostId,
+    /// How far the posts were scrolled.
+    pub scroll_offset: f32,
+}
+
+/// Feeds visited in a tab, like the history of a browser tab.
+#[derive(Debug, Default)]
+pub struct History {
+    back: Vec<Entry>,
+    forward: Vec<Entry>,
+}
+
+impl History {
+    /// Record the feed being left for a new one. Whatever could be gone forward to is dropped.
+    pub fn push(&mut self, entry: Entry) {
+        self.forward.clear();
+        push_capped(&mut self.back, entry);
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.back.is_empty()
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        !self.forward.is_empty()
+    }
+
+    /// The previous feed, with `current` kept to go forward to.
+    pub fn back(&mut self, current: Entry) -> Option<Entry> {
+        let entry = self.back.pop()?;
+        push_capped(&mut self.forward, current);
+
+        Some(entry)
+    }
+
+    /// The next feed, with `current` kept to go back to.
+    pub fn forward(&mut self, current: Entry) -> Option<Entry> {
+        let entry = self.forward.pop()?;
+        push_capped(&mut self.back, current);
+
+        Some(entry)
+    }
+}
+
+fn push_capped(entries: &mut Vec<Entry>, entry: Entry) {
+    if entries.len() >= MAX_ENTRIES {
+        entries.remove(0);
+    }
+
+    entries.push(entry);
+}
@@ -31,6 +31,6 @@ impl ImageManager {
         let image = Image::new(id, size);
         self.images.insert(post_id, image);
 
-        return Some(image);
+        Some(image)
     }
 }
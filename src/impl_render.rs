@@ -1,25 +1,176 @@
-use crate::{image_manager::Image, Render};
+use crate::{
+    image_manager::Image,
+    poll::Poll,
+    selftext::{Block, CodeBlock, SelfText, Span},
+    util::format_duration,
+    Intent, Render,
+};
 use eframe::egui::{self, ScrollArea};
 
 impl Render for Image {
-    fn render(&self, ui: &mut egui::Ui) {
+    fn render(&self, ui: &mut egui::Ui) -> Vec<Intent> {
+        let mut intents = vec![];
+
         ScrollArea::both().show(ui, |ui| {
             ui.vertical_centered_justified(|ui| {
                 let size = egui::Vec2::new(self.size.0 as f32, self.size.1 as f32);
                 let size1 = size * (ui.available_width() / size.x);
                 let size2 = size * (ui.available_height() / size.y);
-                ui.image(self.id, size1.min(size2));
+                let image = egui::Image::new(self.id, size1.min(size2)).sense(egui::Sense::click());
+
+                if ui.add(image).on_hover_text("Next image").clicked() {
+                    intents.push(Intent::NextImage);
+                }
             });
-        })
+        });
+
+        intents
     }
 }
 
 impl Render for String {
-    fn render(&self, ui: &mut egui::Ui) {
+    fn render(&self, ui: &mut egui::Ui) -> Vec<Intent> {
         ScrollArea::vertical().show(ui, |ui| {
             ui.vertical(|ui| {
                 ui.label(self);
             });
         });
+
+        vec![]
+    }
+}
+
+impl Render for SelfText {
+    fn render(&self, ui: &mut egui::Ui) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        ScrollArea::vertical().show(ui, |ui| {
+            ui.vertical(|ui| {
+                for (i, block) in self.blocks.iter().enumerate() {
+                    match block {
+                        Block::Text(spans) => render_spans(spans, ui, &mut intents),
+                        Block::Code(code) => {
+                            render_code_block(code, i, ui);
+                            ui.add_space(5f32);
+                        }
+                    }
+                }
+            });
+        });
+
+        intents
+    }
+}
+
+fn render_spans(spans: &[Span], ui: &mut egui::Ui, intents: &mut Vec<Intent>) {
+    if spans.is_empty() {
+        ui.add_space(5f32);
+        return;
+    }
+
+    ui.horizontal_wrapped(|ui| {
+        ui.spacing_mut().item_spacing.x = 0f32;
+
+        for span in spans {
+            match span {
+                Span::Text(text) => {
+                    ui.label(text);
+                }
+                Span::Link { text, intent } => {
+                    let link = egui::Label::new(text)
+                        .text_color(ui.visuals().hyperlink_color)
+                        .underline()
+                        .sense(egui::Sense::click());
+
+                    if ui.add(link).clicked() {
+                        intents.push(intent.clone());
+                    }
+                }
+            }
+        }
+    });
+}
+
+fn render_code_block(code: &CodeBlock, idx: usize, ui: &mut egui::Ui) {
+    egui::Frame::dark_canvas(ui.style()).show(ui, |ui| {
+        ui.horizontal(|ui| {
+            if let Some(language) = &code.language {
+                ui.small(language);
+            }
+            if ui.small_button("Copy").clicked() {
+                ui.output().copied_text = code.code.clone();
+            }
+        });
+
+        ScrollArea::horizontal()
+            .id_source(("code_block", idx))
+            .show(ui, |ui| {
+                let galley = ui.fonts().layout_job(code.job.clone());
+                let (rect, _) = ui.allocate_exact_size(galley.size(), egui::Sense::hover());
+                ui.painter().galley(rect.min, galley);
+            });
+    });
+}
+
+impl Render for Poll {
+    fn render(&self, ui: &mut egui::Ui) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        ScrollArea::vertical().show(ui, |ui| {
+            ui.vertical(|ui| {
+                if !self.text.is_empty() {
+                    ui.label(&self.text);
+                    ui.add_space(10f32);
+                }
+
+                for option in &self.options {
+                    ui.horizontal(|ui| {
+                        if self.can_vote() && ui.button("Vote").clicked() {
+                            intents.push(Intent::VotePoll {
+                                post_id: self.post_id,
+                                option: option.id.clone(),
+                            });
+                        }
+
+                        let marker = if self.selected.as_ref() == Some(&option.id) {
+                            "✔ "
+                        } else {
+                            ""
+                        };
+
+                        match option.votes {
+                            Some(votes) => {
+                                let fraction = if self.total_votes == 0 {
+                                    0f32
+                                } else {
+                                    votes as f32 / self.total_votes as f32
+                                };
+
+                                ui.add(egui::ProgressBar::new(fraction).text(format!(
+                                    "{}{} - {} votes ({:.0}%)",
+                                    marker,
+                                    option.text,
+                                    votes,
+                                    fraction * 100f32
+                                )));
+                            }
+                            None => {
+                                ui.label(format!("{}{}", marker, option.text));
+                            }
+                        }
+                    });
+                }
+
+                ui.add_space(10f32);
+
+                let status = match self.remaining() {
+                    Some(remaining) => format!("{} left", format_duration(remaining)),
+                    None => String::from("Voting closed"),
+                };
+                ui.label(format!("{} votes\t\t{}", self.total_votes, status));
+            });
+        });
+
+        intents
     }
 }
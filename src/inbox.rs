@@ -0,0 +1,157 @@
+use std::time::Duration;
+
+use snew::things::Message;
+
+/// How often the inbox of the logged in user is checked.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InboxKind {
+    Message,
+    CommentReply,
+    PostReply,
+    Mention,
+}
+
+impl InboxKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InboxKind::Message => "Message",
+            InboxKind::CommentReply => "Comment reply",
+            InboxKind::PostReply => "Post reply",
+            InboxKind::Mention => "Mention",
+        }
+    }
+}
+
+/// A private message, comment reply or mention in the inbox.
+#[derive(Debug, Clone)]
+pub struct InboxItem {
+    /// Fullname, used to mark the item read.
+    pub name: String,
+    pub kind: InboxKind,
+    /// Messages from subreddits have no author.
+    pub author: Option<String>,
+    /// Receiver, for messages the user sent.
+    pub recipient: Option<String>,
+    /// Fullname of the first message of the conversation this message belongs to.
+    pub conversation: String,
+    pub subject: String,
+    pub body: String,
+    /// Permalink to the comment, for replies and mentions.
+    pub context: Option<String>,
+    pub created_utc: f64,
+    pub unread: bool,
+}
+
+impl InboxItem {
+    pub fn new(message: Message) -> Self {
+        let kind = if !message.was_comment {
+            InboxKind::Message
+        } else {
+            match message.kind.as_str() {
+                "username_mention" => InboxKind::Mention,
+                "post_reply" => InboxKind::PostReply,
+                _ => InboxKind::CommentReply,
+            }
+        };
+
+        let conversation = message
+            .first_message_name
+            .unwrap_or_else(|| message.name.clone());
+
+        Self {
+            name: message.name,
+            kind,
+            author: message.author,
+            recipient: message.dest,
+            conversation,
+            subject: message.subject,
+            body: message.body,
+            context: Some(message.context).filter(|context| !context.is_empty()),
+            created_utc: message.created_utc,
+            unread: message.new,
+        }
+    }
+
+    /// Where the item can be read in full: the comment in its thread, or the message.
+    pub fn context_url(&self) -> String {
+        match &self.context {
+            Some(context) => format!("https://www.reddit.com{}", context),
+            None => format!(
+                "https://www.reddit.com/message/messages/{}",
+                self.name.trim_start_matches("t4_")
+            ),
+        }
+    }
+}
+
+/// Private messages sharing a first message, oldest first.
+#[derive(Debug)]
+pub struct Conversation<'a> {
+    /// Fullname of the first message.
+    pub name: &'a str,
+    pub subject: &'a str,
+    /// The user on the other end, if it can be told.
+    pub partner: Option<&'a str>,
+    pub messages: Vec<&'a InboxItem>,
+}
+
+impl Conversation<'_> {
+    pub fn has_unread(&self) -> bool {
+        self.messages.iter().any(|message| message.unread)
+    }
+
+    fn latest(&self) -> f64 {
+        self.messages
+            .last()
+            .map_or(0f64, |message| message.created_utc)
+    }
+}
+
+/// Group received and sent private messages into conversations, most recently active first.
+pub fn conversations<'a>(
+    received: &'a [InboxItem],
+    sent: &'a [InboxItem],
+) -> Vec<Conversation<'a>> {
+    let mut conversations: Vec<Conversation> = vec![];
+
+    // Received messages come first, so their authors are preferred as partners.
+    let messages = received
+        .iter()
+        .map(|message| (message, message.author.as_deref()))
+        .chain(
+            sent.iter()
+                .map(|message| (message, message.recipient.as_deref())),
+        )
+        .filter(|(message, _)| message.kind == InboxKind::Message);
+
+    for (message, partner) in messages {
+        match conversations
+            .iter_mut()
+            .find(|conversation| conversation.name == message.conversation)
+        {
+            Some(conversation) => {
+                conversation.partner = conversation.partner.or(partner);
+                conversation.messages.push(message);
+            }
+            None => conversations.push(Conversation {
+                name: &message.conversation,
+                subject: message.subject.trim_start_matches("re: "),
+                partner,
+                messages: vec![message],
+            }),
+        }
+    }
+
+    for conversation in &mut conversations {
+        conversation
+            .messages
+            .sort_by(|a, b| a.created_utc.total_cmp(&b.created_utc));
+        conversation.messages.dedup_by(|a, b| a.name == b.name);
+    }
+
+    conversations.sort_by(|a, b| b.latest().total_cmp(&a.latest()));
+
+    conversations
+}
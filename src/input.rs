@@ -9,7 +9,7 @@ pub struct KeyBinds {
 
 impl KeyBinds {
     pub fn action(&self, key: KeyPress) -> Option<Action> {
-        self.binds.get(&KeyBind::from(key)).map(|value| *value)
+        self.binds.get(&KeyBind::from(key)).copied()
     }
 }
 
@@ -1,16 +1,34 @@
+mod accounts;
 mod components;
 mod config;
+mod feed;
 mod fetch;
+mod fonts;
+mod history;
 mod image_manager;
 mod impl_render;
+mod inbox;
 mod input;
+mod poll;
+mod profile;
+mod selftext;
 mod state;
+mod tabs;
+mod toast;
+mod util;
 
-use components::{WindowKind, Windows};
+use accounts::{Account, AccountRequest, Accounts, Login, LoginStatus, Session};
+use components::{CommentMove, Focus, PostId, ReplyTarget, Vote, WindowKind, Windows};
+use feed::{FeedSource, Position, Sort, SortOrder};
 use fetch::{Fetcher, Message, MorePosts};
 use image_manager::ImageManager;
 use input::KeyPress;
+use profile::{Profile, ProfileTab};
+use selftext::SelfText;
 use state::State;
+use tabs::{Tab, TabRequest};
+
+use std::{collections::HashMap, time::Instant};
 
 use serde::{Deserialize, Serialize};
 use snew::{
@@ -42,6 +60,18 @@ pub struct SnuiApp {
     /// Logged in user, if any.
     #[serde(skip)]
     user: Option<Me>,
+    /// An intent moved the highlighted post after the feed was rendered, so it should scroll next frame.
+    #[serde(skip)]
+    pending_move: bool,
+    /// When the inbox was last checked.
+    #[serde(skip)]
+    inbox_polled_at: Option<Instant>,
+    /// Clients of accounts that were switched away from, by lowercase account name.
+    #[serde(skip)]
+    sessions: HashMap<String, Session>,
+    /// The token stored by an older version is being exchanged for an account, and must be kept.
+    #[serde(skip)]
+    migrating: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -62,38 +92,56 @@ impl epi::App for SnuiApp {
 
     fn setup(
         &mut self,
-        _ctx: &egui::CtxRef,
+        ctx: &egui::CtxRef,
         _frame: &mut epi::Frame<'_>,
         _storage: Option<&dyn epi::Storage>,
     ) {
+        ctx.set_fonts(fonts::definitions());
+
         if let Some(storage) = _storage {
             if let Some(app) = eframe::epi::get_value(storage, epi::APP_KEY) {
                 *self = app;
             }
+        }
 
-            if let Some(token) =
-                eframe::epi::get_value::<SerializeRefreshToken>(storage, SerializeRefreshToken::ID)
-            {
-                self.client.set_authenticator(UserAuthenticator::new(
-                    token.refresh_token,
-                    Self::CLIENT_ID,
-                ));
+        self.state.accounts = match (Accounts::load(), _storage) {
+            (Ok(Some(accounts)), _) => accounts,
+            (Ok(None), Some(storage)) => self.migrate_accounts(storage),
+            (Ok(None), None) => Accounts::default(),
+            (Err(err), _) => {
+                let text = format!("{}. Accounts will not be saved until it is fixed.", err);
+                self.state.toasts.push(text);
 
-                self.user = self.client.me().ok();
-                self.state.set_feed(self.client.frontpage().hot());
-                self.state.mark_for_refresh = true;
+                Accounts {
+                    unreadable: true,
+                    ..Default::default()
+                }
             }
+        };
+
+        self.state.ensure_tabs();
+
+        let position =
+            self.state.position.take().filter(|position| {
+                self.state.options.restore_session && !position.posts.is_empty()
+            });
+
+        self.switch_account(self.state.accounts.active);
+
+        if let Some(position) = position {
+            self.restore_position(position);
         }
     }
 
     fn save(&mut self, storage: &mut dyn epi::Storage) {
+        self.state.remember_position();
         epi::set_value(storage, epi::APP_KEY, self);
-        if let Some(refresh_token) = self.client.refresh_token() {
-            epi::set_value(
-                storage,
-                SerializeRefreshToken::ID,
-                &SerializeRefreshToken::new(refresh_token),
-            );
+
+        // Tokens were stored here, in plain text, before they got their own file. They are kept
+        // until that file is written with the account they belong to.
+        if !self.migrating && self.store_accounts() {
+            storage.set_string(Accounts::ID, String::new());
+            storage.set_string(SerializeRefreshToken::ID, String::new());
         }
     }
 
@@ -107,13 +155,66 @@ impl epi::App for SnuiApp {
 
         self.conditional_get_more_posts();
 
+        // Switching tabs drops the fetcher's messages, which would lose a feed that is loading.
+        if self.state.feed.is_some() {
+            if let Some(request) = self.state.pending_tab.take() {
+                self.handle_tab_request(request);
+            }
+        }
+
+        if let Some((target, text)) = self.state.pending_reply.take() {
+            self.fetcher.submit_reply(self.client.clone(), target, text);
+        }
+
+        if let Some(submission) = self.state.pending_submission.take() {
+            self.fetcher.submit_post(self.client.clone(), submission);
+        }
+
+        for subreddit in std::mem::take(&mut self.state.pending_flairs) {
+            self.fetcher.get_link_flairs(self.client.clone(), subreddit);
+        }
+
+        let read = std::mem::take(&mut self.state.pending_read);
+        if !read.is_empty() {
+            self.fetcher.mark_read(self.client.clone(), read);
+        }
+
+        self.poll_inbox(frame);
+
+        if let Some(query) = self.state.pending_subreddit_search.take() {
+            self.fetcher.search_subreddits(self.client.clone(), query);
+        }
+
+        for query in std::mem::take(&mut self.state.pending_autocomplete) {
+            self.fetcher
+                .autocomplete_subreddits(self.client.clone(), query);
+        }
+
+        if let Some((name, feed)) = self.state.pending_subreddit.take() {
+            self.fetcher.open_subreddit(feed, name);
+        }
+
+        if let Some(request) = self.state.pending_account.take() {
+            self.handle_account_request(request);
+        }
+
+        if std::mem::take(&mut self.state.pending_sent_fetch) {
+            self.fetcher.get_sent_messages(self.client.clone());
+        }
+
+        if let Some(message) = self.state.pending_message.take() {
+            self.fetcher.send_message(self.client.clone(), message);
+        }
+
         self.state.buffer_posts(&mut self.fetcher);
+        self.state.buffer_comments(&mut self.fetcher);
+        self.buffer_subscriptions();
 
         if self.state.options.immediate_posts {
             self.state.feed_component.set_h_equal_v();
         }
 
-        let mut has_moved = false;
+        let mut has_moved = std::mem::take(&mut self.pending_move);
 
         if self.state.num_request_disable_binds == 0 {
             for event in &ctx.input().events {
@@ -137,14 +238,31 @@ impl epi::App for SnuiApp {
         }
 
         self.try_receive(frame);
+
+        if self.state.open_conversation.is_some() {
+            self.windows.ensure_open(WindowKind::Messages);
+        }
+
         self.windows.update(ctx, &self.client, &mut self.state);
 
-        self.state
-            .render_summary_component(&ctx, self.user.as_ref());
+        let mut intents = self.state.render_summary_component(ctx, self.user.as_ref());
+
+        intents.extend(
+            self.state
+                .render_subscriptions_component(ctx, self.user.is_some()),
+        );
+
+        intents.extend(self.state.render_feed_component(ctx, has_moved));
 
-        self.state.render_feed_component(&ctx, has_moved);
+        intents.extend(self.state.render_comments_component(ctx));
 
-        self.state.render_main_content(&ctx);
+        intents.extend(self.state.render_main_content(ctx));
+
+        intents.extend(self.state.toasts.render(ctx));
+
+        for intent in intents {
+            self.pending_move |= self.handle_intent(ctx, intent);
+        }
 
         if self.fetcher.is_working() {
             ctx.request_repaint();
@@ -157,17 +275,330 @@ impl SnuiApp {
 
     fn conditional_get_more_posts(&mut self) {
         if self.state.feed_component.highlighted
-            >= self
-                .state
-                .get_working_posts()
-                .count()
-                .checked_sub(10)
-                .unwrap_or(0)
+            >= self.state.get_working_posts().count().saturating_sub(10)
         {
             self.get_more_posts()
         }
     }
 
+    fn anonymous_client() -> Reddit {
+        Reddit::new(
+            ApplicationAuthenticator::new(Self::CLIENT_ID),
+            "windows:snui:v0.1.0 (by snui on behalf of anonymous user)",
+        )
+        .expect("Failed to create reddit client")
+    }
+
+    /// A client authenticated with a refresh token. Who it is logged in as is fetched with [`Fetcher::get_me`].
+    fn log_in(refresh_token: String) -> Reddit {
+        let mut client = Self::anonymous_client();
+        client.set_authenticator(UserAuthenticator::new(refresh_token, Self::CLIENT_ID));
+
+        client
+    }
+
+    /// Use another account, or browse anonymously if `idx` is `None`. The feed is reloaded.
+    fn switch_account(&mut self, idx: Option<usize>) {
+        // Keep the session being left, so switching back is fast.
+        if let (Some(account), Some(_)) = (self.state.accounts.active(), &self.user) {
+            let session = Session {
+                client: self.client.clone(),
+                user: self.user.take(),
+            };
+            self.sessions.insert(account.name.to_lowercase(), session);
+        }
+
+        self.state.accounts.active = idx;
+
+        let session = match self.state.accounts.active().cloned() {
+            Some(account) => match self.sessions.remove(&account.name.to_lowercase()) {
+                Some(session) => session,
+                None => {
+                    let client = Self::log_in(account.refresh_token);
+                    self.fetcher.get_me(client.clone(), Some(account.name));
+
+                    Session { client, user: None }
+                }
+            },
+            None => Session {
+                client: Self::anonymous_client(),
+                user: None,
+            },
+        };
+
+        self.client = session.client;
+        self.user = session.user;
+
+        // Everything fetched for the previous account is stale.
+        self.state.reset_account();
+        self.inbox_polled_at = None;
+        self.state.unload_tabs();
+        self.open_source(self.state.source.clone());
+    }
+
+    /// Continue where the feed was left last launch, instead of at the top.
+    fn restore_position(&mut self, position: Position) {
+        let sort = self.state.sort().unwrap_or_default();
+        let mut feed = match self.state.source.feed(&self.client, sort) {
+            Some(feed) => feed,
+            None => return,
+        };
+        feed.after = position.after.clone();
+
+        // The feed is handed back with the posts, so nothing is fetched after them meanwhile.
+        self.state.feed = None;
+        self.state.mark_for_refresh = false;
+
+        self.fetcher
+            .restore_position(self.client.clone(), feed, position);
+    }
+
+    /// Load the feed of a source in the shown tab.
+    fn open_source(&mut self, source: FeedSource) {
+        match source {
+            FeedSource::User(name) => self.open_profile(name),
+            source => self.state.open_feed(&self.client, source),
+        }
+    }
+
+    /// A feed from the history is shown, scrolled to where it was left.
+    fn after_navigation(&mut self) {
+        // Whatever is being fetched belongs to the feed that was left.
+        self.fetcher.reset();
+
+        if self.state.feed.is_none() {
+            if self.state.unfiltered_len() == 0 {
+                self.open_source(self.state.source.clone());
+            } else {
+                self.state.continue_feed(&self.client);
+            }
+        }
+    }
+
+    fn handle_tab_request(&mut self, request: TabRequest) {
+        match request {
+            TabRequest::New(source) => {
+                self.state.tabs.push(Tab::new(source));
+                self.switch_tab(self.state.tabs.len() - 1);
+            }
+            TabRequest::NewWithPost(post) => {
+                let source = FeedSource::Subreddit(post.subreddit.clone());
+                self.handle_tab_request(TabRequest::New(source));
+                self.pending_move |= self.state.show_post(*post);
+            }
+            TabRequest::Switch(idx) => self.switch_tab(idx),
+            TabRequest::Close(idx) => {
+                let len = self.state.tabs.len();
+                if len <= 1 || idx >= len {
+                    return;
+                }
+
+                if idx == self.state.active_tab {
+                    self.switch_tab(if idx + 1 < len { idx + 1 } else { idx - 1 });
+                }
+                self.state.remove_tab(idx);
+            }
+        }
+    }
+
+    fn switch_tab(&mut self, idx: usize) {
+        if idx == self.state.active_tab || idx >= self.state.tabs.len() {
+            return;
+        }
+
+        // Whatever is being fetched belongs to the tab being left.
+        self.fetcher.reset();
+
+        if self.state.switch_tab(idx) {
+            self.open_source(self.state.source.clone());
+        }
+    }
+
+    /// Read accounts from where older versions stored them in eframe's storage.
+    fn migrate_accounts(&mut self, storage: &dyn epi::Storage) -> Accounts {
+        if let Some(accounts) = epi::get_value::<Accounts>(storage, Accounts::ID) {
+            return accounts;
+        }
+
+        // Before multiple accounts, a single token was stored. Its account name is only known after
+        // asking reddit, which adds the account once it answers.
+        if let Some(token) =
+            epi::get_value::<SerializeRefreshToken>(storage, SerializeRefreshToken::ID)
+        {
+            self.fetcher.get_me(Self::log_in(token.refresh_token), None);
+            self.migrating = true;
+        }
+
+        Accounts::default()
+    }
+
+    /// Returns whether the accounts were stored.
+    fn store_accounts(&mut self) -> bool {
+        self.update_refresh_tokens();
+
+        match self.state.accounts.store() {
+            Ok(()) => true,
+            Err(err) => {
+                self.state
+                    .toasts
+                    .push(format!("Could not store accounts: {}", err));
+                false
+            }
+        }
+    }
+
+    /// Revoke the refresh token of an account, and forget everything about it.
+    fn log_out(&mut self, name: String) {
+        self.update_refresh_tokens();
+
+        let idx = match self.state.accounts.position(&name) {
+            Some(idx) => idx,
+            None => return,
+        };
+        let token = self.state.accounts.accounts[idx].refresh_token.clone();
+
+        if self.state.accounts.active == Some(idx) {
+            self.switch_account(None);
+        }
+
+        self.sessions.remove(&name.to_lowercase());
+        self.state.accounts.remove(&name);
+        self.store_accounts();
+
+        self.fetcher.revoke_token(token);
+        self.state.toasts.push(format!("Logged out of /u/{}", name));
+    }
+
+    /// Reddit may hand out new refresh tokens, which replace the stored ones.
+    fn update_refresh_tokens(&mut self) {
+        let clients = self
+            .state
+            .accounts
+            .active()
+            .map(|account| (account.name.to_lowercase(), &self.client))
+            .into_iter()
+            .chain(
+                self.sessions
+                    .iter()
+                    .map(|(name, session)| (name.clone(), &session.client)),
+            );
+
+        let tokens: Vec<(String, String)> = clients
+            .filter_map(|(name, client)| client.refresh_token().map(|token| (name, token)))
+            .collect();
+
+        for (name, token) in tokens {
+            if let Some(idx) = self.state.accounts.position(&name) {
+                self.state.accounts.accounts[idx].refresh_token = token;
+            }
+        }
+    }
+
+    fn handle_account_request(&mut self, request: AccountRequest) {
+        match request {
+            AccountRequest::Switch(name) => {
+                let idx = name.and_then(|name| self.state.accounts.position(&name));
+                self.switch_account(idx);
+            }
+            AccountRequest::Add => self.start_login(),
+            AccountRequest::CancelLogin => {
+                if let Some(login) = &mut self.state.login {
+                    login.cancel();
+                }
+            }
+            AccountRequest::Remove(name) => self.log_out(name),
+        }
+    }
+
+    fn start_login(&mut self) {
+        // Only one login can listen for the redirect at a time.
+        if let Some(login) = &mut self.state.login {
+            login.cancel();
+        }
+
+        let url = self.fetcher.start_login_process();
+
+        self.state.login = Some(Login::new(url));
+        self.windows.ensure_open(WindowKind::Login);
+    }
+
+    /// The user authorized SnUI, and who they are is known.
+    fn finish_login(&mut self, client: Reddit, me: Result<Me, String>) {
+        let status = match (me, client.refresh_token()) {
+            (Ok(me), Some(refresh_token)) => {
+                let name = me.name.clone();
+                let idx = self.state.accounts.insert(Account {
+                    name: name.clone(),
+                    refresh_token,
+                });
+                let session = Session {
+                    client,
+                    user: Some(me),
+                };
+                self.sessions.insert(name.to_lowercase(), session);
+
+                self.switch_account(Some(idx));
+                self.store_accounts();
+
+                self.state.toasts.push(format!("Logged in as /u/{}", name));
+                self.migrating = false;
+                LoginStatus::Done(name)
+            }
+            (Err(err), _) => LoginStatus::Failed(err),
+            (Ok(_), None) => LoginStatus::Failed("Reddit handed out no refresh token".to_owned()),
+        };
+
+        match &mut self.state.login {
+            Some(login) => login.status = status,
+            None => {
+                if let LoginStatus::Failed(err) = status {
+                    self.state.toasts.push(format!("Could not log in: {}", err));
+                }
+            }
+        }
+    }
+
+    /// Check the inbox of the logged in user, if it has not been checked recently.
+    fn poll_inbox(&mut self, frame: &mut epi::Frame<'_>) {
+        if self.user.is_none() {
+            return;
+        }
+
+        let due = self
+            .inbox_polled_at
+            .is_none_or(|at| at.elapsed() >= inbox::POLL_INTERVAL);
+
+        if due {
+            self.inbox_polled_at = Some(Instant::now());
+            self.fetcher.get_inbox(self.client.clone());
+            self.fetcher
+                .repaint_after(frame.repaint_signal(), inbox::POLL_INTERVAL);
+        }
+    }
+
+    /// Fetch the subscriptions of the logged in user, if they are shown and not already fetched.
+    fn buffer_subscriptions(&mut self) {
+        let subscriptions = &mut self.state.subscriptions_component;
+
+        if self.user.is_some() && subscriptions.is_open() && !subscriptions.requested {
+            subscriptions.requested = true;
+            self.fetcher.get_subscriptions(self.client.clone());
+        }
+    }
+
+    fn set_subscribed(&mut self, subreddit: String, subscribed: bool) {
+        let logged_in = self.user.is_some();
+
+        self.state
+            .subscriptions_component
+            .set_subscribed(&subreddit, subscribed, logged_in);
+
+        if logged_in {
+            self.fetcher
+                .set_subscribed(self.client.clone(), subreddit, subscribed);
+        }
+    }
+
     fn handle_action(&mut self, action: Action) -> bool {
         let mut has_moved = false;
         match action {
@@ -177,19 +608,16 @@ impl SnuiApp {
                     .state
                     .feed_component
                     .highlighted
-                    .checked_add(1)
-                    .unwrap_or(usize::MAX)
+                    .saturating_add(1)
                     .min(self.state.unfiltered_len());
+                self.state.focus = Focus::Posts;
 
                 has_moved = true;
             }
             Action::PostUp => {
-                self.state.feed_component.highlighted = self
-                    .state
-                    .feed_component
-                    .highlighted
-                    .checked_sub(1)
-                    .unwrap_or(0);
+                self.state.feed_component.highlighted =
+                    self.state.feed_component.highlighted.saturating_sub(1);
+                self.state.focus = Focus::Posts;
 
                 has_moved = true;
             }
@@ -198,22 +626,270 @@ impl SnuiApp {
                     self.state.feed_component.viewed = self.state.feed_component.highlighted
                 }
             }
-            Action::Login => {
-                self.fetcher.start_login_process();
-            }
+            Action::Login => self.start_login(),
+            Action::Logout => match self.state.accounts.active().map(|a| a.name.clone()) {
+                Some(name) => self.log_out(name),
+                None => self.state.toasts.push("Not logged in"),
+            },
+            Action::CycleAccount => self.switch_account(self.state.accounts.next()),
+            Action::OpenAccountsWindow => self.windows.open(WindowKind::Accounts),
             Action::TogglePostFeedMode => self.state.feed_component.toggle_mode(),
             Action::ToggleMainContentMode => self.state.main_component.toggle_mode(),
             Action::TogglePostSummaryMode => self.state.summary_component.toggle_mode(),
+            Action::ToggleCommentsMode => self.state.comments_component.toggle_mode(),
+            Action::CommentDown => self.state.move_comment_cursor(CommentMove::Next),
+            Action::CommentUp => self.state.move_comment_cursor(CommentMove::Previous),
+            Action::CommentNextSibling => self.state.move_comment_cursor(CommentMove::NextSibling),
+            Action::CommentPreviousSibling => {
+                self.state.move_comment_cursor(CommentMove::PreviousSibling)
+            }
+            Action::CommentParent => self.state.move_comment_cursor(CommentMove::Parent),
+            Action::ToggleCommentCollapse => self.state.toggle_comment_collapse(&mut self.fetcher),
+            Action::CollapseTopLevelComments => self.state.toggle_top_level_collapse(),
+            Action::Upvote => self.vote(Vote::Up),
+            Action::Downvote => self.vote(Vote::Down),
+            Action::ClearVote => self.vote(Vote::None),
+            Action::SaveToggle => {
+                if self.require_login("save posts") {
+                    if let Some((name, saved)) = self.state.toggle_saved() {
+                        self.fetcher.set_saved(self.client.clone(), name, saved);
+                    }
+                }
+            }
+            Action::Hide => {
+                if self.require_login("hide posts") {
+                    if let Some(name) = self.state.hide_highlighted() {
+                        self.fetcher.set_hidden(self.client.clone(), name, true);
+                    }
+                }
+            }
+            Action::OpenInbox => {
+                if self.require_login("read your inbox") {
+                    self.windows.ensure_open(WindowKind::Inbox);
+                }
+            }
+            Action::OpenMessages => {
+                if self.require_login("read your messages") {
+                    self.windows.ensure_open(WindowKind::Messages);
+                }
+            }
+            Action::OpenSubmitWindow => {
+                if self.require_login("submit posts") {
+                    self.windows.ensure_open(WindowKind::Submit);
+                }
+            }
+            Action::ToggleSubscriptionsMode => self.state.subscriptions_component.toggle_mode(),
+            Action::SavedFeed => {
+                if let Some(user) = &self.user {
+                    self.open_profile(user.name.clone());
+                    self.set_profile_tab(ProfileTab::Saved);
+                } else {
+                    self.state.toasts.push("Log in to see saved posts");
+                }
+            }
+            Action::OpenOwnProfile => {
+                if let Some(user) = &self.user {
+                    self.open_profile(user.name.clone());
+                } else {
+                    self.state.toasts.push("Log in to see your profile");
+                }
+            }
+            Action::CycleProfileTab => {
+                if let Some(profile) = &self.state.profile {
+                    self.set_profile_tab(profile.tab.next(profile.own));
+                }
+            }
+            Action::ReplyToPost => self.open_composer(self.state.post_reply_target()),
+            Action::ReplyToComment => self.open_composer(self.state.comment_reply_target()),
+            Action::CycleCommentSort => {
+                let sort = &mut self.state.comments_component.sort;
+                *sort = sort.next();
+            }
             Action::OpenSubredditWindow => self.windows.open(WindowKind::Subreddit),
             Action::OpenFilterWindow => self.windows.open(WindowKind::Filter),
-            Action::Frontpage => {
-                self.state.reset_feed(self.client.frontpage().hot());
+            Action::Frontpage => self.state.open_feed(&self.client, FeedSource::Frontpage),
+            Action::CycleSort => {
+                if let Some(sort) = self.state.source.search_sort() {
+                    let kind = sort.kind.next();
+                    self.state
+                        .set_search_sort(&self.client, Sort { kind, ..sort });
+                } else if let Some(sort) = self.state.sort() {
+                    let kind = sort.kind.next(&self.state.source);
+                    self.state.set_sort(&self.client, Sort { kind, ..sort });
+                }
+            }
+            Action::CycleTimeRange => {
+                if let Some(sort) = self.state.source.search_sort() {
+                    if sort.kind.has_range() {
+                        let range = sort.range.next();
+                        self.state
+                            .set_search_sort(&self.client, Sort { range, ..sort });
+                    }
+                } else if let Some(sort) = self.state.sort().filter(|sort| sort.kind.has_range()) {
+                    let range = sort.range.next();
+                    self.state.set_sort(&self.client, Sort { range, ..sort });
+                }
+            }
+            Action::OpenSortWindow => self.windows.open(WindowKind::Sort),
+            Action::OpenSearchWindow => self.windows.open(WindowKind::Search),
+            Action::Back => {
+                if self.state.go_back() {
+                    self.after_navigation();
+                } else {
+                    self.state.toasts.push("Nothing to go back to");
+                }
+            }
+            Action::Forward => {
+                if self.state.go_forward() {
+                    self.after_navigation();
+                } else {
+                    self.state.toasts.push("Nothing to go forward to");
+                }
+            }
+            Action::NewTab => {
+                self.state.pending_tab = Some(TabRequest::New(FeedSource::Frontpage));
+            }
+            Action::CloseTab => {
+                self.state.pending_tab = Some(TabRequest::Close(self.state.active_tab));
+            }
+            Action::NextTab => {
+                let idx = (self.state.active_tab + 1) % self.state.tabs.len();
+                self.state.pending_tab = Some(TabRequest::Switch(idx));
+            }
+            Action::PreviousTab => {
+                let len = self.state.tabs.len();
+                let idx = (self.state.active_tab + len - 1) % len;
+                self.state.pending_tab = Some(TabRequest::Switch(idx));
+            }
+            Action::OpenCrosspost => {
+                let post_id = self
+                    .state
+                    .viewed_post()
+                    .filter(|post| post.crosspost.is_some())
+                    .map(|post| post.post_id);
+
+                if let Some(post_id) = post_id {
+                    self.state.comments_component.original_of = Some(post_id);
+                    if !self.state.comments_component.is_open() {
+                        self.state.comments_component.toggle_mode();
+                    }
+                }
             }
         };
 
         has_moved
     }
 
+    /// Whether a user is logged in. If not, tell the user they need to be to do `what`.
+    fn require_login(&mut self, what: &str) -> bool {
+        if self.user.is_none() {
+            self.state.toasts.push(format!("Log in to {}", what));
+        }
+
+        self.user.is_some()
+    }
+
+    fn vote(&mut self, vote: Vote) {
+        if !self.require_login("vote") {
+            return;
+        }
+
+        if let Some(request) = self.state.vote(vote) {
+            self.fetcher.vote(self.client.clone(), request);
+        }
+    }
+
+    /// Show the history of a user in the feed, starting with the overview.
+    fn open_profile(&mut self, name: String) {
+        let own = self
+            .user
+            .as_ref()
+            .is_some_and(|me| me.name.eq_ignore_ascii_case(&name));
+
+        self.state.open_profile(
+            Profile::new(name.clone(), own),
+            self.client.user(&name).submitted(),
+        );
+        self.fetcher
+            .get_user_about(self.client.clone(), name.clone());
+        let comments = self.client.user(&name).comments();
+        self.fetcher.get_user_comments(name, comments);
+    }
+
+    fn set_profile_tab(&mut self, tab: ProfileTab) {
+        let mut profile = match self.state.profile.take() {
+            Some(profile) => profile,
+            None => return,
+        };
+
+        let previous = std::mem::replace(&mut profile.tab, tab);
+
+        let shares_feed = Profile::shows_submitted(previous) && Profile::shows_submitted(tab);
+
+        if shares_feed {
+            self.state.profile = Some(profile);
+        } else {
+            let feed = profile.feed(&self.client);
+            self.state.open_profile(profile, feed);
+        }
+    }
+
+    fn open_composer(&mut self, target: Option<ReplyTarget>) {
+        if target.is_some() {
+            self.state.reply_target = target;
+            self.windows.ensure_open(WindowKind::Composer);
+        }
+    }
+
+    fn handle_intent(&mut self, ctx: &egui::CtxRef, intent: Intent) -> bool {
+        let mut has_moved = false;
+        match intent {
+            Intent::Action(action) => has_moved = self.handle_action(action),
+            Intent::OpenUrl(url) => ctx.output().open_url(url),
+            Intent::OpenSubreddit(name) => {
+                self.state
+                    .open_feed(&self.client, FeedSource::Subreddit(name));
+            }
+            Intent::OpenUser(name) => self.open_profile(name),
+            Intent::ProfileTab(tab) => self.set_profile_tab(tab),
+            Intent::MoreUserComments => {
+                if let Some((name, feed)) = self.state.take_comment_feed() {
+                    self.fetcher.get_user_comments(name, feed);
+                }
+            }
+            Intent::NextImage => has_moved = self.state.highlight_next_image(),
+            Intent::SetSubscribed {
+                subreddit,
+                subscribed,
+            } => self.set_subscribed(subreddit, subscribed),
+            Intent::SetHidden { name, hidden } => {
+                self.state.set_hidden(&name, hidden);
+                self.fetcher.set_hidden(self.client.clone(), name, hidden);
+            }
+            Intent::LoadMoreComments(parent_id) => {
+                self.state.load_more_comments(&parent_id, &mut self.fetcher)
+            }
+            Intent::VotePoll { post_id, option } => {
+                if self.require_login("vote") {
+                    if let Some(post) = self.state.get_post(post_id) {
+                        self.fetcher.vote_poll(
+                            post.content_post().clone(),
+                            option.clone(),
+                            post_id,
+                        );
+                        self.state.record_poll_vote(post_id, &option);
+                    }
+                }
+            }
+        };
+
+        if has_moved {
+            ctx.request_repaint();
+        }
+
+        has_moved
+    }
+
     fn try_receive(&mut self, frame: &mut epi::Frame) {
         if let Some(message) = self.fetcher.try_recv() {
             match message {
@@ -223,7 +899,10 @@ impl SnuiApp {
                 }
                 Message::ContentReady(content, post_id) => match content {
                     snew::content::Content::Text(text) => {
-                        self.state.set_content(&post_id, Box::new(text));
+                        self.state.set_content(
+                            &post_id,
+                            Box::new(SelfText::new(&text, &self.state.highlighter)),
+                        );
                     }
                     snew::content::Content::Image(image) => {
                         self.fetcher.decode_image(image, post_id);
@@ -235,7 +914,13 @@ impl SnuiApp {
                         );
                     }
                 },
-                Message::ImageDecoded(image, size, url) => {
+                Message::ImageDecoded(None, post_id) => {
+                    self.state.set_content(
+                        &post_id,
+                        Box::new(String::from("Sorry, this image could not be decoded.")),
+                    );
+                }
+                Message::ImageDecoded(Some((image, size)), url) => {
                     let handle = self.image_manager.store(
                         self.state.feed_component.highlighted,
                         image,
@@ -246,8 +931,152 @@ impl SnuiApp {
                         self.state.set_content(&url, Box::new(handle));
                     }
                 }
-                Message::UserLoggedIn(auth) => {
-                    self.client.set_authenticator(auth);
+                Message::UserLoggedIn(url, auth) => {
+                    // Logins that were cancelled or started over are ignored.
+                    let login = self.state.login.as_mut().filter(|login| {
+                        login.url.as_deref() == Some(url.as_str())
+                            && matches!(login.status, LoginStatus::Waiting)
+                    });
+
+                    match (login, auth) {
+                        (Some(login), Ok(auth)) => {
+                            login.status = LoginStatus::FetchingUser;
+
+                            let mut client = Self::anonymous_client();
+                            client.set_authenticator(auth);
+                            self.fetcher.get_me(client, None);
+                        }
+                        (Some(login), Err(err)) => login.status = LoginStatus::Failed(err),
+                        (None, _) => {}
+                    }
+                }
+                Message::MeReady(None, client, me) => self.finish_login(client, me),
+                Message::MeReady(Some(name), _, me) => {
+                    let active = self.state.accounts.active();
+
+                    if active.is_some_and(|account| account.name.eq_ignore_ascii_case(&name)) {
+                        match me {
+                            Ok(me) => self.user = Some(me),
+                            Err(_) => {
+                                let text = format!("Could not log in as /u/{}", name);
+                                self.state.toasts.push(text);
+                            }
+                        }
+                    }
+                }
+                Message::SubscriptionsReady(result) => match result {
+                    Ok(subscriptions) => {
+                        for subscription in &subscriptions {
+                            if let Some(url) = &subscription.icon_url {
+                                self.fetcher
+                                    .get_icon(subscription.name.to_lowercase(), url.clone());
+                            }
+                        }
+
+                        self.state.subscriptions_component.remote = Some(subscriptions);
+                    }
+                    Err(err) => {
+                        self.state.subscriptions_component.remote = Some(vec![]);
+                        self.state
+                            .toasts
+                            .push(format!("Could not load subscriptions: {}", err));
+                    }
+                },
+                Message::SubscribedChanged(subreddit, subscribed, result) => {
+                    if let Err(err) = result {
+                        // Refetch, rather than guess what the list looked like.
+                        self.state.subscriptions_component.reset_remote();
+
+                        let verb = if subscribed {
+                            "Subscribing to"
+                        } else {
+                            "Unsubscribing from"
+                        };
+                        self.state
+                            .toasts
+                            .push(format!("{} r/{} failed: {}", verb, subreddit, err));
+                    }
+                }
+                Message::LinkFlairsReady(subreddit, flairs) => {
+                    self.state.set_link_flairs(&subreddit, flairs)
+                }
+                Message::PostSubmitted(result) => self.state.post_submitted(result),
+                Message::InboxReady(inbox) => self.state.set_inbox(inbox),
+                Message::MarkedRead(names, result) => self.state.marked_read(names, result),
+                Message::SentMessagesReady(sent) => self.state.set_sent_messages(sent),
+                Message::PrivateMessageSent(message, result) => {
+                    self.state.message_sent(message, result)
+                }
+                Message::PositionRestored(feed, highlighted, result) => match result {
+                    Ok(posts) => {
+                        self.state.set_feed(feed);
+                        self.state.extend_posts(posts);
+
+                        if let Some(name) = highlighted {
+                            self.state.highlight_post(&name);
+                            self.pending_move = true;
+                        }
+                    }
+                    Err(err) => {
+                        let text = format!("Could not continue where you left off: {}", err);
+                        self.state.toasts.push(text);
+                        self.open_source(self.state.source.clone());
+                    }
+                },
+                Message::SubredditsFound(query, results) => {
+                    self.state.subreddits_found(&query, results)
+                }
+                Message::SubredditsAutocompleted(query, names) => {
+                    self.state.subreddits_autocompleted(&query, names)
+                }
+                Message::SubredditOpened(name, result) => {
+                    self.state.subreddit_opened(&name, result)
+                }
+                Message::TokenRevoked(result) => {
+                    if let Err(err) = result {
+                        self.state
+                            .toasts
+                            .push(format!("Revoking the token with reddit failed: {}", err));
+                    }
+                }
+                Message::UserAboutReady(name, about) => self.state.set_profile_about(&name, about),
+                Message::UserCommentsReady(name, feed, comments) => {
+                    self.state.set_profile_comments(&name, feed, comments)
+                }
+                // Subreddits whose icon failed are shown without one.
+                Message::IconDecoded(_, None) => {}
+                Message::IconDecoded(subreddit, Some((icon, size))) => {
+                    let id = frame.tex_allocator().alloc_srgba_premultiplied(size, &icon);
+                    self.state
+                        .subscriptions_component
+                        .icons
+                        .insert(subreddit, image_manager::Image::new(id, size));
+                }
+                Message::CommentsReady(key, result) => match result {
+                    Ok(comments) => self.state.set_comments(key, comments),
+                    Err(err) => self.state.comments_failed(key, err),
+                },
+                Message::MoreCommentsReady(key, parent_id, result) => {
+                    self.state.expand_more_comments(key, &parent_id, result)
+                }
+                Message::ReplySubmitted(target, result) => {
+                    self.state.reply_submitted(target, result)
+                }
+                Message::Voted(request, result) => {
+                    if let Err(err) = result {
+                        self.state.vote_failed(request, err);
+                    }
+                }
+                Message::SavedChanged(name, saved, result) => {
+                    self.state.saved_changed(&name, saved, result)
+                }
+                Message::HiddenChanged(name, hidden, result) => {
+                    self.state.hidden_changed(&name, hidden, result)
+                }
+                Message::PollVoted(post_id, result) => {
+                    if let Err(err) = result {
+                        self.state.poll_vote_failed(post_id, err);
+                    }
                 }
             }
         }
@@ -261,7 +1090,61 @@ impl SnuiApp {
 /// Something that can be rendered.
 /// If it makes sense to render something in multiple ways, this should be the "main", most common sense way.
 pub trait Render: std::fmt::Debug {
-    fn render(&self, ui: &mut egui::Ui);
+    /// Returns what the user asked for by interacting with the content, e.g. clicking a link.
+    fn render(&self, ui: &mut egui::Ui) -> Vec<Intent>;
+}
+
+/// Something content wants the app to do. Dispatched by [`SnuiApp`] like keybound [`Action`]s.
+#[derive(Debug, Clone)]
+pub enum Intent {
+    /// Perform an action, as if its key was pressed
+    Action(Action),
+    /// Open a url in the browser
+    OpenUrl(String),
+    /// Load a subreddit into the feed
+    OpenSubreddit(String),
+    /// Show the history of a user in the feed
+    OpenUser(String),
+    /// Switch tab of the shown profile
+    ProfileTab(ProfileTab),
+    /// Fetch older comments of the shown profile
+    MoreUserComments,
+    /// Highlight the next post with an image
+    NextImage,
+    /// Vote for an option in a poll
+    VotePoll { post_id: PostId, option: String },
+    /// Expand the "load more" stub below the comment or post with this fullname
+    LoadMoreComments(String),
+    /// Hide or unhide the post with this fullname
+    SetHidden { name: String, hidden: bool },
+    /// Subscribe to or unsubscribe from a subreddit
+    SetSubscribed { subreddit: String, subscribed: bool },
+}
+
+impl Intent {
+    /// Links to subreddits and users are opened in snui, everything else in the browser.
+    pub fn from_url(url: &str) -> Self {
+        let path = url
+            .trim_start_matches("https://")
+            .trim_start_matches("http://");
+        let path = ["www.reddit.com", "old.reddit.com", "reddit.com"]
+            .iter()
+            .find_map(|host| path.strip_prefix(host))
+            .or_else(|| url.starts_with('/').then_some(url));
+
+        let path = match path {
+            Some(path) => path,
+            None => return Self::OpenUrl(url.to_owned()),
+        };
+
+        let segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+
+        match segments[..] {
+            ["r", name] => Self::OpenSubreddit(name.to_owned()),
+            ["u", name] | ["user", name] => Self::OpenUser(name.to_owned()),
+            _ => Self::OpenUrl(format!("https://www.reddit.com{}", path)),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, Deserialize)]
@@ -274,6 +1157,26 @@ pub enum Action {
     OpenPost,
     /// Go to frontpage
     Frontpage,
+    /// Sort the feed by the next of best, hot, new, top, rising and controversial
+    CycleSort,
+    /// Switch the time range of top and controversial sorts
+    CycleTimeRange,
+    /// Open the window for picking the sort of the feed
+    OpenSortWindow,
+    /// Open the window for searching posts and subreddits
+    OpenSearchWindow,
+    /// Return to the previous feed, where it was left
+    Back,
+    /// Undo going back
+    Forward,
+    /// Open the frontpage in a new tab
+    NewTab,
+    /// Close the shown tab
+    CloseTab,
+    /// Show the tab to the right, wrapping around
+    NextTab,
+    /// Show the tab to the left, wrapping around
+    PreviousTab,
     /// Open subreddit window
     OpenSubredditWindow,
     /// Open filter window
@@ -286,15 +1189,80 @@ pub enum Action {
     ToggleMainContentMode,
     /// Toggle mode for post summary
     TogglePostSummaryMode,
+    /// Show the comments of the original of the currently viewed crosspost
+    OpenCrosspost,
+    /// Toggle mode for the comments
+    ToggleCommentsMode,
+    /// Currently marked comment should be the next one
+    CommentDown,
+    /// Currently marked comment should be the previous one
+    CommentUp,
+    /// Currently marked comment should be the next one with the same parent
+    CommentNextSibling,
+    /// Currently marked comment should be the previous one with the same parent
+    CommentPreviousSibling,
+    /// Currently marked comment should be its parent
+    CommentParent,
+    /// Collapse or expand the replies to the currently marked comment
+    ToggleCommentCollapse,
+    /// Collapse or expand all top level comments
+    CollapseTopLevelComments,
+    /// Fetch comments in the next sort order
+    CycleCommentSort,
+    /// Write a reply to the currently viewed post
+    ReplyToPost,
+    /// Write a reply to the currently marked comment
+    ReplyToComment,
+    /// Upvote the currently marked post or comment
+    Upvote,
+    /// Downvote the currently marked post or comment
+    Downvote,
+    /// Remove the vote on the currently marked post or comment
+    ClearVote,
+    /// Save or unsave the currently marked post
+    SaveToggle,
+    /// Hide the currently marked post
+    Hide,
+    /// Go to the saved posts of the logged in user
+    SavedFeed,
+    /// Cycle the subscriptions panel between snapped, floating and closed
+    ToggleSubscriptionsMode,
+    /// Open the window for submitting a new post
+    OpenSubmitWindow,
+    /// Open the inbox of the logged in user
+    OpenInbox,
+    /// Open the private message conversations of the logged in user
+    OpenMessages,
+    /// Show the history of the logged in user in the feed
+    OpenOwnProfile,
+    /// Switch between overview, submitted, comments, saved and upvoted of the shown profile
+    CycleProfileTab,
+    /// Log out of the active account, revoking its token
+    Logout,
+    /// Switch to the next account, or anonymous browsing after the last
+    CycleAccount,
+    /// Open the window for switching, adding and removing accounts
+    OpenAccountsWindow,
+}
+
+/// Actions of the messages window, performed by its own keybinds.
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub enum MessageAction {
+    /// Show the previous conversation
+    ConversationUp,
+    /// Show the next conversation
+    ConversationDown,
+    /// Start typing a reply in the shown conversation
+    Reply,
+    /// Start a new conversation
+    Compose,
+    /// Close the messages window
+    Close,
 }
 
 impl Default for SnuiApp {
     fn default() -> Self {
-        let client = Reddit::new(
-            ApplicationAuthenticator::new("kt3c_AvYiWqN5dO1lzMbjg"),
-            "windows:snui:v0.1.0 (by snui on behalf of anonymous user)",
-        )
-        .expect("Failed to create reddit client");
+        let client = Self::anonymous_client();
 
         let mut feed = client.frontpage().hot();
         feed.limit = 15;
@@ -306,6 +1274,10 @@ impl Default for SnuiApp {
             fetcher: Fetcher::default(),
             windows: Windows::new(),
             user: None,
+            pending_move: false,
+            inbox_polled_at: None,
+            sessions: HashMap::new(),
+            migrating: false,
         }
     }
 }
@@ -328,6 +1300,7 @@ impl From<reddit::Error> for Error {
     }
 }
 
+/// How the refresh token was stored before multiple accounts. Only read, to migrate it.
 #[derive(Debug, Serialize, Deserialize)]
 struct SerializeRefreshToken {
     refresh_token: String,
@@ -335,10 +1308,4 @@ struct SerializeRefreshToken {
 
 impl SerializeRefreshToken {
     const ID: &'static str = "Refresh token";
-
-    fn new(token: String) -> Self {
-        Self {
-            refresh_token: token,
-        }
-    }
 }
@@ -0,0 +1,73 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use snew::things::PollData;
+
+use crate::components::PostId;
+
+/// A poll post, with its options and results.
+#[derive(Debug)]
+pub struct Poll {
+    pub post_id: PostId,
+    /// Selftext of the poll post.
+    pub text: String,
+    pub options: Vec<PollOption>,
+    pub total_votes: u64,
+    pub ends_at: SystemTime,
+    /// Id of the option the user voted for.
+    pub selected: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct PollOption {
+    pub id: String,
+    pub text: String,
+    /// Reddit hides the results until you have voted or the poll has ended.
+    pub votes: Option<u64>,
+}
+
+impl Poll {
+    pub fn new(post_id: PostId, text: Option<&String>, data: &PollData) -> Self {
+        Self {
+            post_id,
+            text: text.cloned().unwrap_or_default(),
+            options: data
+                .options
+                .iter()
+                .map(|option| PollOption {
+                    id: option.id.clone(),
+                    text: option.text.clone(),
+                    votes: option.vote_count,
+                })
+                .collect(),
+            total_votes: data.total_vote_count,
+            ends_at: UNIX_EPOCH + Duration::from_millis(data.voting_end_timestamp),
+            selected: data.user_selection.clone(),
+        }
+    }
+
+    /// Time left to vote, or `None` if the poll has ended.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.ends_at.duration_since(SystemTime::now()).ok()
+    }
+
+    pub fn can_vote(&self) -> bool {
+        self.selected.is_none() && self.remaining().is_some()
+    }
+
+    /// Record a vote, without refetching the post.
+    pub fn vote(&mut self, option_id: &str) {
+        self.selected = Some(option_id.to_owned());
+        self.total_votes += 1;
+
+        // Hidden results stay hidden until the post is refetched.
+        if let Some(option) = self
+            .options
+            .iter_mut()
+            .find(|option| option.id == option_id)
+        {
+            if let Some(votes) = option.votes.as_mut() {
+                *votes += 1;
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use snew::{
+    reddit::Reddit,
+    things::{Comment, CommentFeed, PostFeed},
+};
+
+/// What part of a user's history the feed shows. Comments replace the posts in the feed, or are
+/// shown between them on the overview.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileTab {
+    Overview,
+    Submitted,
+    Comments,
+    Saved,
+    Upvoted,
+}
+
+impl ProfileTab {
+    /// Saved and upvoted are private, so only shown on your own profile.
+    pub fn available(own: bool) -> &'static [ProfileTab] {
+        if own {
+            &[
+                ProfileTab::Overview,
+                ProfileTab::Submitted,
+                ProfileTab::Comments,
+                ProfileTab::Saved,
+                ProfileTab::Upvoted,
+            ]
+        } else {
+            &[
+                ProfileTab::Overview,
+                ProfileTab::Submitted,
+                ProfileTab::Comments,
+            ]
+        }
+    }
+
+    pub fn next(&self, own: bool) -> Self {
+        let tabs = Self::available(own);
+        let idx = tabs.iter().position(|tab| tab == self).unwrap_or(0);
+
+        tabs[(idx + 1) % tabs.len()]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ProfileTab::Overview => "Overview",
+            ProfileTab::Submitted => "Submitted",
+            ProfileTab::Comments => "Comments",
+            ProfileTab::Saved => "Saved",
+            ProfileTab::Upvoted => "Upvoted",
+        }
+    }
+}
+
+/// Public information about a user.
+#[derive(Debug, Clone)]
+pub struct About {
+    pub link_karma: i64,
+    pub comment_karma: i64,
+    pub created_utc: f64,
+}
+
+/// A comment in a user's history, with the post it was made on.
+#[derive(Debug, Clone)]
+pub struct UserComment {
+    pub body: String,
+    pub score: i64,
+    pub created_utc: f64,
+    pub subreddit: String,
+    pub link_title: String,
+    pub permalink: String,
+}
+
+impl From<Comment> for UserComment {
+    fn from(comment: Comment) -> Self {
+        Self {
+            body: comment.body,
+            score: comment.score,
+            created_utc: comment.created_utc,
+            subreddit: comment.subreddit,
+            link_title: comment.link_title,
+            permalink: comment.permalink,
+        }
+    }
+}
+
+/// The user whose history the feed shows.
+#[derive(Debug)]
+pub struct Profile {
+    pub name: String,
+    /// Whether this is the logged in user.
+    pub own: bool,
+    pub tab: ProfileTab,
+    /// `None` until fetched.
+    pub about: Option<About>,
+    /// `None` until fetched.
+    pub comments: Option<Vec<UserComment>>,
+    /// Continues after the fetched comments. Taken while more are fetched, like the feed of posts.
+    pub comment_feed: Option<CommentFeed>,
+    /// Whether reddit has comments that are not fetched yet.
+    pub more_comments: bool,
+}
+
+impl Profile {
+    /// Comments fetched at a time.
+    pub const COMMENT_PAGE: usize = 25;
+
+    pub fn new(name: String, own: bool) -> Self {
+        Self {
+            name,
+            own,
+            tab: ProfileTab::Overview,
+            about: None,
+            comments: None,
+            comment_feed: None,
+            more_comments: true,
+        }
+    }
+
+    /// The posts of the tab. Overview, submitted and comments share the feed of submitted posts.
+    pub fn feed(&self, reddit: &Reddit) -> PostFeed {
+        let user = reddit.user(&self.name);
+
+        match self.tab {
+            ProfileTab::Saved => user.saved(),
+            ProfileTab::Upvoted => user.upvoted(),
+            ProfileTab::Overview | ProfileTab::Submitted | ProfileTab::Comments => user.submitted(),
+        }
+    }
+
+    /// Whether the tab shows the feed of submitted posts.
+    pub fn shows_submitted(tab: ProfileTab) -> bool {
+        matches!(
+            tab,
+            ProfileTab::Overview | ProfileTab::Submitted | ProfileTab::Comments
+        )
+    }
+
+    /// Add comments reddit sent for the comment feed, and hand the feed back.
+    pub fn extend_comments(&mut self, comments: Vec<UserComment>, feed: CommentFeed) {
+        self.more_comments = comments.len() >= Self::COMMENT_PAGE;
+        self.comments.get_or_insert_with(Vec::new).extend(comments);
+        self.comment_feed = Some(feed);
+    }
+}
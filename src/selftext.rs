@@ -0,0 +1,350 @@
+use crate::Intent;
+use eframe::egui::{
+    self,
+    text::{LayoutJob, TextFormat},
+    TextStyle,
+};
+
+use syntect::{
+    easy::HighlightLines,
+    highlighting::{Theme, ThemeSet},
+    parsing::{SyntaxReference, SyntaxSet},
+    util::LinesWithEndings,
+};
+
+/// The selftext of a post, split into prose and code blocks.
+#[derive(Debug)]
+pub struct SelfText {
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug)]
+pub enum Block {
+    /// A single line of prose.
+    Text(Vec<Span>),
+    Code(CodeBlock),
+}
+
+#[derive(Debug)]
+pub enum Span {
+    Text(String),
+    Link { text: String, intent: Intent },
+}
+
+#[derive(Debug)]
+pub struct CodeBlock {
+    /// Name of the detected language, if any.
+    pub language: Option<String>,
+    /// The raw code, as it should be copied.
+    pub code: String,
+    /// The highlighted code, ready to be laid out.
+    pub job: LayoutJob,
+}
+
+impl SelfText {
+    pub fn new(text: &str, highlighter: &Highlighter) -> Self {
+        let blocks = split_blocks(text)
+            .into_iter()
+            .flat_map(|block| match block {
+                RawBlock::Text(text) => text.lines().map(|line| Block::Text(spans(line))).collect(),
+                RawBlock::Code { tag, code } => vec![Block::Code(highlighter.highlight(tag, code))],
+            })
+            .collect();
+
+        Self { blocks }
+    }
+}
+
+/// Syntax highlighting for code blocks. Loading the syntaxes is slow, so this is created once.
+pub struct Highlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+impl std::fmt::Debug for Highlighter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Highlighter").finish()
+    }
+}
+
+impl Default for Highlighter {
+    fn default() -> Self {
+        let mut themes = ThemeSet::load_defaults();
+
+        Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme: themes
+                .themes
+                .remove("base16-mocha.dark")
+                .expect("Default theme missing"),
+        }
+    }
+}
+
+impl Highlighter {
+    pub fn highlight(&self, tag: Option<String>, code: String) -> CodeBlock {
+        let syntax = tag
+            .as_deref()
+            .and_then(|tag| self.syntaxes.find_syntax_by_token(tag))
+            .or_else(|| self.detect(&code));
+
+        let mut job = LayoutJob::default();
+
+        match syntax {
+            Some(syntax) => {
+                let mut lines = HighlightLines::new(syntax, &self.theme);
+
+                for line in LinesWithEndings::from(&code) {
+                    match lines.highlight_line(line, &self.syntaxes) {
+                        Ok(ranges) => {
+                            for (style, text) in ranges {
+                                let fg = style.foreground;
+                                let color = egui::Color32::from_rgb(fg.r, fg.g, fg.b);
+                                job.append(
+                                    text,
+                                    0f32,
+                                    TextFormat::simple(TextStyle::Monospace, color),
+                                );
+                            }
+                        }
+                        Err(_) => job.append(line, 0f32, Self::plain()),
+                    }
+                }
+            }
+            None => job.append(&code, 0f32, Self::plain()),
+        }
+
+        CodeBlock {
+            language: syntax.map(|syntax| syntax.name.clone()),
+            code,
+            job,
+        }
+    }
+
+    fn plain() -> TextFormat {
+        TextFormat::simple(TextStyle::Monospace, egui::Color32::LIGHT_GRAY)
+    }
+
+    /// Guess the language of an untagged code block.
+    fn detect(&self, code: &str) -> Option<&SyntaxReference> {
+        if let Some(syntax) = code
+            .lines()
+            .next()
+            .and_then(|line| self.syntaxes.find_syntax_by_first_line(line))
+        {
+            return Some(syntax);
+        }
+
+        let has = |needles: &[&str]| needles.iter().all(|needle| code.contains(needle));
+
+        let token = if has(&["fn ", "let "]) || has(&["impl ", "{"]) {
+            "rs"
+        } else if has(&["def ", ":"]) || has(&["import ", "self"]) {
+            "py"
+        } else if has(&["#include"]) {
+            "cpp"
+        } else if has(&["package ", "func "]) {
+            "go"
+        } else if has(&["public ", "class "]) {
+            "java"
+        } else if has(&["function"]) || has(&["const ", "=>"]) {
+            "js"
+        } else if has(&["SELECT ", "FROM "]) {
+            "sql"
+        } else if has(&["</"]) {
+            "html"
+        } else if has(&["$ "]) {
+            "sh"
+        } else {
+            return None;
+        };
+
+        self.syntaxes.find_syntax_by_token(token)
+    }
+}
+
+enum RawBlock {
+    Text(String),
+    Code { tag: Option<String>, code: String },
+}
+
+/// Split markdown into prose and code blocks. Both fenced (``` and ~~~) and indented blocks are recognized.
+fn split_blocks(text: &str) -> Vec<RawBlock> {
+    let mut blocks = vec![];
+    let mut prose = String::new();
+    let mut lines = text.lines().peekable();
+    let mut previous_blank = true;
+
+    let flush = |prose: &mut String, blocks: &mut Vec<RawBlock>| {
+        let text = prose.trim_matches('\n');
+        if !text.is_empty() {
+            blocks.push(RawBlock::Text(text.to_owned()));
+        }
+        prose.clear();
+    };
+
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim_start();
+
+        if let Some(fence) = ["```", "~~~"]
+            .into_iter()
+            .find(|fence| trimmed.starts_with(*fence))
+        {
+            flush(&mut prose, &mut blocks);
+
+            let tag = trimmed.trim_start_matches(fence).trim();
+            let tag = (!tag.is_empty()).then(|| tag.to_owned());
+
+            let mut code = String::new();
+            for line in lines.by_ref() {
+                if line.trim_start().starts_with(fence) {
+                    break;
+                }
+                code.push_str(line);
+                code.push('\n');
+            }
+
+            blocks.push(RawBlock::Code { tag, code });
+            previous_blank = true;
+        } else if previous_blank && is_indented(line) {
+            flush(&mut prose, &mut blocks);
+
+            let mut code = String::new();
+            code.push_str(unindent(line));
+            code.push('\n');
+
+            while let Some(line) = lines.next_if(|line| is_indented(line) || line.trim().is_empty())
+            {
+                code.push_str(unindent(line));
+                code.push('\n');
+            }
+
+            let code = code.trim_end_matches('\n').to_owned() + "\n";
+            blocks.push(RawBlock::Code { tag: None, code });
+            previous_blank = true;
+        } else {
+            prose.push_str(line);
+            prose.push('\n');
+            previous_blank = line.trim().is_empty();
+        }
+    }
+
+    flush(&mut prose, &mut blocks);
+
+    blocks
+}
+
+fn is_indented(line: &str) -> bool {
+    (line.starts_with("    ") || line.starts_with('\t')) && !line.trim().is_empty()
+}
+
+fn unindent(line: &str) -> &str {
+    line.strip_prefix("    ")
+        .or_else(|| line.strip_prefix('\t'))
+        .unwrap_or(line)
+}
+
+/// Split a line of prose into plain text and links. Markdown links, bare urls and
+/// subreddit and user references (`/r/rust`, `u/spez`) are recognized.
+fn spans(line: &str) -> Vec<Span> {
+    let mut spans = vec![];
+    let mut text = String::new();
+    let mut rest = line;
+
+    while let Some(c) = rest.chars().next() {
+        let at_word_start = (text.is_empty() && spans.is_empty())
+            || text.ends_with(|c: char| c.is_whitespace() || c == '(');
+
+        let link = markdown_link(rest)
+            .or_else(|| bare_url(rest))
+            .or_else(|| at_word_start.then(|| reference(rest)).flatten());
+
+        match link {
+            Some((len, span)) => {
+                if !text.is_empty() {
+                    spans.push(Span::Text(std::mem::take(&mut text)));
+                }
+                spans.push(span);
+                rest = &rest[len..];
+            }
+            None => {
+                text.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+    }
+
+    if !text.is_empty() {
+        spans.push(Span::Text(text));
+    }
+
+    spans
+}
+
+/// `[text](url)`
+fn markdown_link(s: &str) -> Option<(usize, Span)> {
+    let s = s.strip_prefix('[')?;
+    let text_end = s.find("](")?;
+    let url_end = s[text_end..].find(')')? + text_end;
+
+    let text = &s[..text_end];
+    let url = &s[text_end + 2..url_end];
+
+    if text.contains('[') || url.contains(char::is_whitespace) {
+        return None;
+    }
+
+    let span = Span::Link {
+        text: text.to_owned(),
+        intent: Intent::from_url(url),
+    };
+
+    Some((url_end + 2, span))
+}
+
+/// `https://example.com`
+fn bare_url(s: &str) -> Option<(usize, Span)> {
+    if !(s.starts_with("https://") || s.starts_with("http://")) {
+        return None;
+    }
+
+    let len = s.find(char::is_whitespace).unwrap_or(s.len());
+    // Trailing punctuation is most likely part of the sentence.
+    let url = s[..len].trim_end_matches(['.', ',', ')', '!', '?', ':', ';']);
+
+    let span = Span::Link {
+        text: url.to_owned(),
+        intent: Intent::from_url(url),
+    };
+
+    Some((url.len(), span))
+}
+
+/// `/r/name`, `r/name`, `/u/name` or `u/name`
+fn reference(s: &str) -> Option<(usize, Span)> {
+    let leading = if s.starts_with('/') { 1 } else { 0 };
+    let kind = s[leading..].get(..2)?;
+    let name = &s[leading + 2..];
+    let name_len = name
+        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(name.len());
+
+    if name_len == 0 {
+        return None;
+    }
+
+    let name = name[..name_len].to_owned();
+    let intent = match kind {
+        "r/" => Intent::OpenSubreddit(name),
+        "u/" => Intent::OpenUser(name),
+        _ => return None,
+    };
+
+    let len = leading + 2 + name_len;
+    let span = Span::Link {
+        text: s[..len].to_owned(),
+        intent,
+    };
+
+    Some((len, span))
+}
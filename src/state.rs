@@ -1,17 +1,36 @@
-use std::collections::HashMap;
+use std::{
+    collections::{hash_map, HashMap, HashSet},
+    sync::Arc,
+};
 
 use eframe::egui::CtxRef;
 use lru::LruCache;
 use serde::{Deserialize, Serialize};
-use snew::things::{Me, Post, PostFeed};
+use snew::{
+    reddit::Reddit,
+    things::{CommentFeed, Me, Post, PostFeed, Reply},
+};
 
 use crate::{
+    accounts::{AccountRequest, Accounts, Login},
     components::{
-        MainContentComponent, PostFeedComponent, PostId, PostSummaryComponent, ViewablePost,
+        CommentMove, CommentTree, CommentsComponent, CommentsKey, FeedHeader, Flair, Focus,
+        MainContentComponent, OutgoingMessage, PostFeedComponent, PostId, PostSummaryComponent,
+        ReplyStatus, ReplyTarget, Submission, SubmitError, SubmitStatus, SubredditCheck,
+        SubredditInfo, SubredditSearch, SubscriptionsComponent, ViewablePost, Vote, VoteRequest,
     },
     config::Options,
+    feed::{FeedSource, Position, SearchSort, Sort},
     fetch::Fetcher,
-    Render,
+    history::{Entry, History},
+    inbox::InboxItem,
+    poll::Poll,
+    profile::{About, Profile, UserComment},
+    selftext::Highlighter,
+    tabs::{Tab, TabRequest, CONTENT_CACHE_SIZE},
+    toast::Toasts,
+    util::fuzzy_score,
+    Intent, Render,
 };
 
 #[derive(Deserialize, Serialize)]
@@ -22,14 +41,125 @@ pub struct State {
     pub main_component: MainContentComponent,
     /// The summary of the the current post. Also information about the user, if present.
     pub summary_component: PostSummaryComponent,
+    /// The comments of the current post.
+    #[serde(default = "CommentsComponent::new")]
+    pub comments_component: CommentsComponent,
+    /// Subscribed subreddits.
+    #[serde(default = "SubscriptionsComponent::new")]
+    pub subscriptions_component: SubscriptionsComponent,
     /// Currently loaded feed.
     #[serde(skip)]
     pub feed: Option<PostFeed>,
+    /// Where the posts of the feed come from.
+    #[serde(default)]
+    pub source: FeedSource,
+    /// Open tabs. The one at `active_tab` is shown, and its feed is kept in the fields of the state.
+    #[serde(default)]
+    pub tabs: Vec<Tab>,
+    #[serde(default)]
+    pub active_tab: usize,
+    /// What the tab strip wants done, once the feed is not loading.
+    #[serde(skip)]
+    pub pending_tab: Option<TabRequest>,
+    /// Feeds visited in the shown tab.
+    #[serde(skip)]
+    pub history: History,
+    /// The last search for subreddits.
+    #[serde(skip)]
+    pub subreddit_search: Option<SubredditSearch>,
+    /// Query the search window wants subreddits found for.
+    #[serde(skip)]
+    pub pending_subreddit_search: Option<String>,
+    /// Subreddits opened lately, most recent first.
+    #[serde(default)]
+    pub recent_subreddits: Vec<String>,
+    /// Subreddit names reddit completed queries to, by lowercase query.
+    #[serde(skip)]
+    pub autocompleted: HashMap<String, Vec<String>>,
+    /// Lowercase queries reddit is completing. Failed ones are asked again.
+    #[serde(skip)]
+    pub autocompleting: HashSet<String>,
+    /// Queries subreddit names should be completed for.
+    #[serde(skip)]
+    pub pending_autocomplete: Vec<String>,
+    /// The subreddit the subreddit window is opening.
+    #[serde(skip)]
+    pub subreddit_check: Option<SubredditCheck>,
+    /// Subreddit whose first posts should be fetched before it is shown, and its feed.
+    #[serde(skip)]
+    pub pending_subreddit: Option<(String, PostFeed)>,
+    /// Where the shown feed was left. Written when saving, and read on launch.
+    #[serde(default)]
+    pub position: Option<Position>,
+    /// Sorts chosen for the frontpage and subreddits, by [`FeedSource::sort_key`].
+    #[serde(default)]
+    pub sorts: HashMap<String, Sort>,
+    /// Accounts the user has logged in with. Stored apart from the rest of the state.
+    #[serde(skip)]
+    pub accounts: Accounts,
+    /// What the accounts window wants done.
+    #[serde(skip)]
+    pub pending_account: Option<AccountRequest>,
+    /// The login in progress, or the outcome of the last one.
+    #[serde(skip)]
+    pub login: Option<Login>,
+    /// The user whose history the feed shows, if any.
+    #[serde(skip)]
+    pub profile: Option<Profile>,
     /// Posts that are fetched and can be displayed
     #[serde(skip)]
     posts: Vec<ViewablePost>,
     #[serde(skip)]
+    #[serde(default = "default_filters")]
     pub active_filters: HashMap<u32, fn(&&ViewablePost) -> bool>,
+    /// Comments of the viewed post.
+    #[serde(skip)]
+    comments: Option<CommentTree>,
+    /// What the composer replies to.
+    #[serde(skip)]
+    pub reply_target: Option<ReplyTarget>,
+    /// Unsent replies, by the fullname of what they reply to.
+    #[serde(default)]
+    pub drafts: HashMap<String, String>,
+    /// Reply the composer wants submitted.
+    #[serde(skip)]
+    pub pending_reply: Option<(ReplyTarget, String)>,
+    /// Progress of the last submitted reply.
+    #[serde(skip)]
+    pub reply_status: Option<ReplyStatus>,
+    /// Post the submit window wants submitted.
+    #[serde(skip)]
+    pub pending_submission: Option<Submission>,
+    /// Progress of the last submitted post.
+    #[serde(skip)]
+    pub submit_status: Option<SubmitStatus>,
+    /// Link flairs of subreddits, by lowercase name. Empty while fetching.
+    #[serde(skip)]
+    pub link_flairs: HashMap<String, Vec<Flair>>,
+    /// Subreddits whose link flairs should be fetched.
+    #[serde(skip)]
+    pub pending_flairs: Vec<String>,
+    /// Inbox of the logged in user, `None` until fetched.
+    #[serde(skip)]
+    pub inbox: Option<Vec<InboxItem>>,
+    /// Inbox items, by fullname, that should be marked read.
+    #[serde(skip)]
+    pub pending_read: Vec<String>,
+    /// Private messages the user sent, `None` until fetched.
+    #[serde(skip)]
+    pub sent_messages: Option<Vec<InboxItem>>,
+    /// Sent messages should be fetched.
+    #[serde(skip)]
+    pub pending_sent_fetch: bool,
+    /// Private message the messages window wants sent.
+    #[serde(skip)]
+    pub pending_message: Option<OutgoingMessage>,
+    /// Progress of the last sent private message.
+    #[serde(skip)]
+    pub message_status: Option<ReplyStatus>,
+    /// Conversation, by fullname of its first message, the messages window should show.
+    #[serde(skip)]
+    pub open_conversation: Option<String>,
     /// Cached content
     #[serde(skip)]
     #[serde(default = "empty_map")]
@@ -43,6 +173,16 @@ pub struct State {
     /// User options
     #[serde(skip)]
     pub options: Options,
+    /// Syntax highlighter for code in selftext
+    #[serde(skip)]
+    pub highlighter: Highlighter,
+    /// Notifications for the user
+    #[serde(skip)]
+    pub toasts: Toasts,
+    /// Whether posts or comments were navigated last
+    #[serde(skip)]
+    #[serde(default = "posts_focus")]
+    pub focus: Focus,
 }
 
 impl std::fmt::Debug for State {
@@ -51,24 +191,63 @@ impl std::fmt::Debug for State {
             .field("feed_component", &self.feed_component)
             .field("main_component", &self.main_component)
             .field("summary_component", &self.summary_component)
+            .field("comments_component", &self.comments_component)
+            .field("subscriptions_component", &self.subscriptions_component)
             .field("feed", &self.feed)
+            .field("source", &self.source)
+            .field("tabs", &self.tabs)
+            .field("active_tab", &self.active_tab)
+            .field("pending_tab", &self.pending_tab)
+            .field("history", &self.history)
+            .field("subreddit_search", &self.subreddit_search)
+            .field("pending_subreddit_search", &self.pending_subreddit_search)
+            .field("recent_subreddits", &self.recent_subreddits)
+            .field("autocompleted", &self.autocompleted)
+            .field("autocompleting", &self.autocompleting)
+            .field("pending_autocomplete", &self.pending_autocomplete)
+            .field("subreddit_check", &self.subreddit_check)
+            .field("pending_subreddit", &self.pending_subreddit)
+            .field("position", &self.position)
+            .field("sorts", &self.sorts)
+            .field("accounts", &self.accounts.accounts.len())
+            .field("pending_account", &self.pending_account)
+            .field("login", &self.login)
+            .field("profile", &self.profile)
             .field("posts", &self.posts)
+            .field("comments", &self.comments)
+            .field("reply_target", &self.reply_target)
+            .field("drafts", &self.drafts)
+            .field("pending_reply", &self.pending_reply)
+            .field("reply_status", &self.reply_status)
+            .field("pending_submission", &self.pending_submission)
+            .field("submit_status", &self.submit_status)
+            .field("link_flairs", &self.link_flairs)
+            .field("pending_flairs", &self.pending_flairs)
+            .field("inbox", &self.inbox)
+            .field("pending_read", &self.pending_read)
+            .field("sent_messages", &self.sent_messages)
+            .field("pending_sent_fetch", &self.pending_sent_fetch)
+            .field("pending_message", &self.pending_message)
+            .field("message_status", &self.message_status)
+            .field("open_conversation", &self.open_conversation)
             .field("content_cache", &self.content_cache)
             .field("num_request_disable_binds", &self.num_request_disable_binds)
             .field("mark_for_refresh", &self.mark_for_refresh)
             .field("options", &self.options)
+            .field("toasts", &self.toasts)
+            .field("focus", &self.focus)
             .finish()
     }
 }
 
-fn current_buffer<'a, T>(vec: &'a Vec<T>, idx: usize, amount: usize, ratio: f32) -> &'a [T] {
+fn current_buffer<T>(vec: &[T], idx: usize, amount: usize, ratio: f32) -> &[T] {
     let right_side = (ratio * amount as f32).round() as usize;
     let left_side = ((1f32 - ratio) * amount as f32).round() as usize;
 
     let len = vec.len();
 
     if len <= amount {
-        &vec[..]
+        vec
     } else if idx.checked_sub(left_side).is_none() {
         &vec[0..idx + right_side]
     } else if idx + right_side > len {
@@ -84,23 +263,209 @@ impl State {
             feed_component: PostFeedComponent::new(),
             main_component: MainContentComponent::new(),
             summary_component: PostSummaryComponent::new(),
+            comments_component: CommentsComponent::new(),
+            subscriptions_component: SubscriptionsComponent::new(),
             feed: Some(feed),
+            source: FeedSource::Frontpage,
+            tabs: vec![Tab::new(FeedSource::Frontpage)],
+            active_tab: 0,
+            pending_tab: None,
+            history: History::default(),
+            subreddit_search: None,
+            pending_subreddit_search: None,
+            recent_subreddits: vec![],
+            autocompleted: HashMap::new(),
+            autocompleting: HashSet::new(),
+            pending_autocomplete: vec![],
+            subreddit_check: None,
+            pending_subreddit: None,
+            position: None,
+            sorts: HashMap::new(),
+            accounts: Default::default(),
+            pending_account: None,
+            login: None,
+            profile: None,
             posts: vec![],
-            active_filters: HashMap::new(),
+            comments: None,
+            reply_target: None,
+            drafts: HashMap::new(),
+            pending_reply: None,
+            reply_status: None,
+            pending_submission: None,
+            submit_status: None,
+            link_flairs: HashMap::new(),
+            pending_flairs: vec![],
+            inbox: None,
+            pending_read: vec![],
+            sent_messages: None,
+            pending_sent_fetch: false,
+            pending_message: None,
+            message_status: None,
+            open_conversation: None,
+            active_filters: default_filters(),
             num_request_disable_binds: 0,
             mark_for_refresh: true,
-            content_cache: LruCache::new(250),
+            content_cache: empty_map(),
             options: Default::default(),
+            highlighter: Default::default(),
+            toasts: Default::default(),
+            focus: Focus::Posts,
         }
     }
     pub fn reset_feed(&mut self, new_feed: PostFeed) {
+        if !self.posts.is_empty() {
+            let entry = self.take_entry();
+            self.history.push(entry);
+        }
+
         self.feed = Some(new_feed);
+        self.profile = None;
         self.posts.clear();
+        self.comments = None;
         self.content_cache.clear();
         self.feed_component.reset();
 
+        // Resetting the fetcher drops the first posts of a subreddit being checked.
+        if matches!(&self.subreddit_check, Some(check) if check.result.is_none()) {
+            self.subreddit_check = None;
+        }
+
         self.mark_for_refresh = true;
     }
+
+    /// Move the shown feed out, to be kept in the history.
+    fn take_entry(&mut self) -> Entry {
+        Entry {
+            source: self.source.clone(),
+            sort: self.sort(),
+            feed: self.feed.take(),
+            posts: std::mem::take(&mut self.posts),
+            profile: self.profile.take(),
+            highlighted: self.feed_component.highlighted,
+            scroll_offset: self.feed_component.scroll_offset,
+        }
+    }
+
+    /// A new cursor for the shown feed, continuing after its last post. For feeds that were
+    /// loading when left, as the cursor of a loading feed is with the request.
+    pub fn continue_feed(&mut self, reddit: &Reddit) {
+        let feed = match &self.profile {
+            Some(profile) => Some(profile.feed(reddit)),
+            None => self.source.feed(reddit, self.sort_of(&self.source)),
+        };
+
+        self.feed = feed.map(|mut feed| {
+            feed.after = self.posts.last().map(|post| post.inner.name.clone());
+            feed
+        });
+    }
+
+    pub fn search_subreddits(&mut self, query: String) {
+        self.subreddit_search = Some(SubredditSearch {
+            query: query.clone(),
+            results: None,
+        });
+        self.pending_subreddit_search = Some(query);
+    }
+
+    pub fn subreddits_found(&mut self, query: &str, results: Result<Vec<SubredditInfo>, String>) {
+        // Results of an older search are dropped.
+        if let Some(search) = self
+            .subreddit_search
+            .as_mut()
+            .filter(|search| search.query == query)
+        {
+            search.results = Some(results);
+        }
+    }
+
+    /// Remember where the shown feed is, to continue there on the next launch. Profiles start over.
+    pub fn remember_position(&mut self) {
+        if let FeedSource::User(_) = self.source {
+            self.position = None;
+            return;
+        }
+
+        // The feed is taken while more posts load, so the cursor comes from the posts, as in
+        // `continue_feed`.
+        let after = self.posts.last().map(|post| post.inner.name.clone());
+
+        // The cursor continues after the last post, so the newest posts are kept.
+        let skip = self.posts.len().saturating_sub(Position::MAX_POSTS);
+        let posts = self.posts[skip..]
+            .iter()
+            .map(|post| post.inner.name.clone())
+            .collect();
+
+        let highlighted = self
+            .get_working_posts()
+            .nth(self.feed_component.highlighted)
+            .map(|post| post.inner.name.clone());
+
+        self.position = Some(Position {
+            after,
+            posts,
+            highlighted,
+        });
+    }
+
+    /// Highlight the post with this fullname, if it is shown.
+    pub fn highlight_post(&mut self, name: &str) {
+        let idx = self
+            .get_working_posts()
+            .position(|post| post.inner.name == name);
+        if let Some(idx) = idx {
+            self.feed_component.highlighted = idx;
+            self.feed_component.viewed = idx;
+        }
+    }
+
+    /// Return to the previous feed. Returns whether there was one.
+    pub fn go_back(&mut self) -> bool {
+        if !self.history.can_go_back() {
+            return false;
+        }
+
+        let current = self.take_entry();
+        let entry = self.history.back(current);
+
+        self.restore_entry(entry)
+    }
+
+    /// Return to the feed gone back from. Returns whether there was one.
+    pub fn go_forward(&mut self) -> bool {
+        if !self.history.can_go_forward() {
+            return false;
+        }
+
+        let current = self.take_entry();
+        let entry = self.history.forward(current);
+
+        self.restore_entry(entry)
+    }
+
+    fn restore_entry(&mut self, entry: Option<Entry>) -> bool {
+        let entry = match entry {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        if let (Some(key), Some(sort)) = (entry.source.sort_key(), entry.sort) {
+            self.sorts.insert(key, sort);
+        }
+
+        self.source = entry.source;
+        self.feed = entry.feed;
+        self.posts = entry.posts;
+        self.profile = entry.profile;
+        self.comments = None;
+        self.content_cache.clear();
+        self.feed_component.highlighted = entry.highlighted;
+        self.feed_component.viewed = entry.highlighted;
+        self.feed_component.restore_scroll = Some(entry.scroll_offset);
+
+        true
+    }
     pub fn get_working_posts(&self) -> impl Iterator<Item = &ViewablePost> {
         Self::filter_posts(&self.posts, &self.active_filters)
     }
@@ -110,7 +475,7 @@ impl State {
     }
 
     pub fn buffer_posts(&mut self, fetcher: &mut Fetcher) {
-        let current = Self::filter_posts(&self.posts, &self.active_filters).collect();
+        let current: Vec<_> = Self::filter_posts(&self.posts, &self.active_filters).collect();
         let window = current_buffer(
             &current,
             self.feed_component.viewed,
@@ -120,12 +485,727 @@ impl State {
 
         for post in window {
             if !self.content_cache.contains(&post.post_id) {
-                self.content_cache.put(post.post_id, None);
-                fetcher.get_content(post.inner.clone(), post.post_id)
+                let inner = post.content_post();
+
+                // Polls are part of the post, there is nothing to fetch.
+                if let Some(poll) = &inner.poll_data {
+                    let poll = Poll::new(post.post_id, inner.selftext.as_ref(), poll);
+                    self.content_cache.put(post.post_id, Some(Box::new(poll)));
+                } else {
+                    self.content_cache.put(post.post_id, None);
+                    fetcher.get_content(inner.clone(), post.post_id)
+                }
+            }
+        }
+    }
+
+    /// Fetch the comments of the viewed post, if they are shown and not already fetched.
+    pub fn buffer_comments(&mut self, fetcher: &mut Fetcher) {
+        if !self.comments_component.is_open() {
+            return;
+        }
+
+        let post = match self.viewed_post() {
+            Some(post) => post,
+            None => return,
+        };
+
+        let original =
+            post.crosspost.is_some() && self.comments_component.original_of == Some(post.post_id);
+        let key = CommentsKey {
+            post_id: post.post_id,
+            original,
+            sort: self.comments_component.sort,
+        };
+
+        if self.comments.as_ref().map(|tree| tree.key) != Some(key) {
+            if let Some(source) = self.comments_source(key) {
+                fetcher.get_comments(source, key);
+                self.comments = Some(CommentTree::loading(key));
+            }
+        }
+    }
+
+    /// The post comments with this key belong to.
+    fn comments_source(&self, key: CommentsKey) -> Option<Arc<Post>> {
+        let post = self.get_post(key.post_id)?;
+
+        if key.original {
+            Some(post.content_post().clone())
+        } else {
+            Some(post.inner.clone())
+        }
+    }
+
+    /// Fetch the comments the "load more" stub below `parent_id` stands for.
+    pub fn load_more_comments(&mut self, parent_id: &str, fetcher: &mut Fetcher) {
+        let key = match self.comments.as_ref() {
+            Some(tree) => tree.key,
+            None => return,
+        };
+
+        let source = match self.comments_source(key) {
+            Some(source) => source,
+            None => return,
+        };
+
+        if let Some(more) = self
+            .comments
+            .as_mut()
+            .and_then(|tree| tree.start_loading_more(parent_id))
+        {
+            fetcher.get_more_comments(source, key, more);
+        }
+    }
+
+    pub fn expand_more_comments(
+        &mut self,
+        key: CommentsKey,
+        parent_id: &str,
+        replies: Result<Vec<Reply>, String>,
+    ) {
+        if let Some(tree) = self.comments.as_mut().filter(|tree| tree.key == key) {
+            match replies {
+                Ok(replies) => tree.expand_more(parent_id, replies),
+                Err(err) => {
+                    tree.failed_loading_more(parent_id);
+                    self.toasts
+                        .push(format!("Loading more comments failed: {}", err));
+                }
+            }
+        }
+    }
+
+    pub fn move_comment_cursor(&mut self, movement: CommentMove) {
+        if let Some(tree) = self.comments.as_mut() {
+            tree.move_cursor(movement);
+            self.comments_component.scroll_to_highlighted = true;
+            self.focus = Focus::Comments;
+        }
+    }
+
+    /// Collapse or expand the highlighted comment. If it is a "load more" stub, load it instead.
+    pub fn toggle_comment_collapse(&mut self, fetcher: &mut Fetcher) {
+        let stub = self
+            .comments
+            .as_ref()
+            .and_then(|tree| tree.highlighted_stub())
+            .map(|stub| stub.parent_id.clone());
+
+        match stub {
+            Some(parent_id) => self.load_more_comments(&parent_id, fetcher),
+            None => {
+                if let Some(tree) = self.comments.as_mut() {
+                    tree.toggle_collapse();
+                }
             }
         }
     }
 
+    pub fn toggle_top_level_collapse(&mut self) {
+        if let Some(tree) = self.comments.as_mut() {
+            tree.toggle_collapse_top_level();
+            self.comments_component.scroll_to_highlighted = true;
+        }
+    }
+
+    /// Apply a vote to the highlighted post or comment, before reddit has accepted it.
+    /// Returns what should be sent to reddit.
+    pub fn vote(&mut self, vote: Vote) -> Option<VoteRequest> {
+        match self.focus {
+            Focus::Comments => {
+                let tree = self.comments.as_mut()?;
+                let name = tree
+                    .comments
+                    .as_ref()?
+                    .get(tree.highlighted)
+                    .filter(|comment| comment.more.is_none())?
+                    .name
+                    .clone();
+                let previous = tree.set_vote(&name, vote)?;
+
+                Some(VoteRequest {
+                    name,
+                    vote,
+                    previous,
+                })
+            }
+            Focus::Posts => {
+                let post = self.highlighted_post_mut()?;
+                let previous = std::mem::replace(&mut post.vote, vote);
+
+                Some(VoteRequest {
+                    name: post.inner.name.clone(),
+                    vote,
+                    previous,
+                })
+            }
+        }
+    }
+
+    fn highlighted_post_mut(&mut self) -> Option<&mut ViewablePost> {
+        let post_id = self
+            .get_working_posts()
+            .nth(self.feed_component.highlighted)?
+            .post_id;
+
+        self.posts.iter_mut().find(|post| post.post_id == post_id)
+    }
+
+    fn post_by_name_mut(&mut self, name: &str) -> Option<&mut ViewablePost> {
+        self.posts.iter_mut().find(|post| post.inner.name == name)
+    }
+
+    /// Toggle whether the highlighted post is saved. Returns its name and whether it is now saved.
+    pub fn toggle_saved(&mut self) -> Option<(String, bool)> {
+        let post = self.highlighted_post_mut()?;
+        post.saved = !post.saved;
+
+        Some((post.inner.name.clone(), post.saved))
+    }
+
+    /// Handle reddit's answer to saving or unsaving a post.
+    pub fn saved_changed(&mut self, name: &str, saved: bool, result: Result<(), String>) {
+        match result {
+            Ok(()) if saved => self.toasts.push("Saved"),
+            Ok(()) => self.toasts.push("Unsaved"),
+            Err(err) => {
+                if let Some(post) = self.post_by_name_mut(name) {
+                    post.saved = !saved;
+                }
+                self.toasts.push(format!("Saving failed: {}", err));
+            }
+        }
+    }
+
+    /// Hide the highlighted post. Returns its name.
+    pub fn hide_highlighted(&mut self) -> Option<String> {
+        let post = self.highlighted_post_mut()?;
+        post.hidden = true;
+        let name = post.inner.name.clone();
+
+        self.toasts.push_with_action(
+            "Post hidden",
+            "Undo",
+            Intent::SetHidden {
+                name: name.clone(),
+                hidden: false,
+            },
+        );
+
+        Some(name)
+    }
+
+    pub fn set_hidden(&mut self, name: &str, hidden: bool) {
+        if let Some(post) = self.post_by_name_mut(name) {
+            post.hidden = hidden;
+        }
+    }
+
+    /// Handle reddit's answer to hiding or unhiding a post.
+    pub fn hidden_changed(&mut self, name: &str, hidden: bool, result: Result<(), String>) {
+        if let Err(err) = result {
+            self.set_hidden(name, !hidden);
+            self.toasts.push(format!("Hiding failed: {}", err));
+        }
+    }
+
+    /// Roll back a vote reddit rejected.
+    pub fn vote_failed(&mut self, request: VoteRequest, err: String) {
+        if let Some(post) = self.post_by_name_mut(&request.name) {
+            post.vote = request.previous;
+        }
+
+        if let Some(tree) = self.comments.as_mut() {
+            tree.set_vote(&request.name, request.previous);
+        }
+
+        self.toasts.push(format!("Vote failed: {}", err));
+    }
+
+    /// Target for replying to the viewed post. If the comments of a crosspost's original are shown, reply to that.
+    pub fn post_reply_target(&self) -> Option<ReplyTarget> {
+        let post = self.viewed_post()?;
+        let key = self
+            .comments
+            .as_ref()
+            .map(|tree| tree.key)
+            .filter(|key| key.post_id == post.post_id);
+
+        let source = match key {
+            Some(key) => self.comments_source(key)?,
+            None => post.inner.clone(),
+        };
+
+        Some(ReplyTarget {
+            name: source.name.clone(),
+            title: format!("\"{}\"", source.title),
+            key,
+        })
+    }
+
+    /// Target for replying to the highlighted comment.
+    pub fn comment_reply_target(&self) -> Option<ReplyTarget> {
+        let tree = self.comments.as_ref()?;
+        let comment = tree
+            .comments
+            .as_ref()?
+            .get(tree.highlighted)
+            .filter(|comment| comment.more.is_none())?;
+
+        Some(ReplyTarget {
+            name: comment.name.clone(),
+            title: format!("/u/{}", comment.author),
+            key: Some(tree.key),
+        })
+    }
+
+    /// Handle the result of submitting a reply.
+    pub fn reply_submitted(&mut self, target: ReplyTarget, result: Result<Reply, String>) {
+        match result {
+            Ok(reply) => {
+                self.drafts.remove(&target.name);
+                self.reply_status = None;
+
+                if let Some(tree) = self
+                    .comments
+                    .as_mut()
+                    .filter(|tree| Some(tree.key) == target.key)
+                {
+                    tree.insert_reply(&target.name, reply);
+                    self.comments_component.scroll_to_highlighted = true;
+                }
+
+                if self.reply_target.as_ref().map(|t| &t.name) == Some(&target.name) {
+                    self.reply_target = None;
+                }
+            }
+            Err(err) => self.reply_status = Some(ReplyStatus::Failed(err)),
+        }
+    }
+
+    /// Fetch the link flairs of a subreddit, unless they already are.
+    pub fn request_link_flairs(&mut self, subreddit: &str) {
+        let key = subreddit.to_lowercase();
+
+        if let hash_map::Entry::Vacant(entry) = self.link_flairs.entry(key) {
+            entry.insert(vec![]);
+            self.pending_flairs.push(subreddit.to_owned());
+        }
+    }
+
+    /// Subreddits whose flairs failed to load are left without flairs.
+    pub fn set_link_flairs(&mut self, subreddit: &str, flairs: Result<Vec<Flair>, String>) {
+        if let Ok(flairs) = flairs {
+            self.link_flairs.insert(subreddit.to_lowercase(), flairs);
+        }
+    }
+
+    /// Handle reddit's answer to a submission. The new post is opened in a tab of its own.
+    pub fn post_submitted(&mut self, result: Result<Post, Vec<SubmitError>>) {
+        match result {
+            Ok(post) => {
+                self.submit_status = Some(SubmitStatus::Done);
+                self.pending_tab = Some(TabRequest::NewWithPost(Box::new(post)));
+            }
+            Err(errors) => self.submit_status = Some(SubmitStatus::Failed(errors)),
+        }
+    }
+
+    /// Add a post to the shown feed, and view it. Returns whether the highlighted post moved.
+    pub fn show_post(&mut self, post: Post) -> bool {
+        let post_id = self.posts.len();
+        self.posts.push((post_id, post).into());
+
+        let idx = self
+            .get_working_posts()
+            .position(|post| post.post_id == post_id);
+        match idx {
+            Some(idx) => {
+                self.feed_component.highlighted = idx;
+                self.feed_component.viewed = idx;
+                self.focus = Focus::Posts;
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn unread_count(&self) -> usize {
+        self.inbox
+            .iter()
+            .flatten()
+            .filter(|item| item.unread)
+            .count()
+    }
+
+    /// Mark inbox items read locally, and ask for reddit to be told.
+    pub fn mark_read(&mut self, names: Vec<String>) {
+        self.set_unread(&names, false);
+        self.pending_read.extend(names);
+    }
+
+    fn set_unread(&mut self, names: &[String], unread: bool) {
+        for item in self.inbox.iter_mut().flatten() {
+            if names.contains(&item.name) {
+                item.unread = unread;
+            }
+        }
+    }
+
+    /// Handle reddit's answer to marking inbox items read.
+    pub fn marked_read(&mut self, names: Vec<String>, result: Result<(), String>) {
+        if let Err(err) = result {
+            self.set_unread(&names, true);
+            self.toasts.push(format!("Marking read failed: {}", err));
+        }
+    }
+
+    pub fn set_inbox(&mut self, inbox: Result<Vec<InboxItem>, String>) {
+        match inbox {
+            Ok(inbox) => self.inbox = Some(inbox),
+            Err(err) => self
+                .toasts
+                .push(format!("Loading the inbox failed: {}", err)),
+        }
+    }
+
+    pub fn set_sent_messages(&mut self, sent: Result<Vec<InboxItem>, String>) {
+        match sent {
+            Ok(sent) => self.sent_messages = Some(sent),
+            Err(err) => self
+                .toasts
+                .push(format!("Loading sent messages failed: {}", err)),
+        }
+    }
+
+    /// Handle reddit's answer to sending a private message.
+    pub fn message_sent(&mut self, message: OutgoingMessage, result: Result<(), String>) {
+        match result {
+            Ok(()) => {
+                if let Some((_, conversation)) = &message.reply_to {
+                    self.drafts.remove(conversation);
+                }
+                self.message_status = None;
+                // The sent message is shown once it is fetched back.
+                self.pending_sent_fetch = true;
+            }
+            Err(err) => self.message_status = Some(ReplyStatus::Failed(err)),
+        }
+    }
+
+    /// Forget what was fetched for the previous account.
+    pub fn reset_account(&mut self) {
+        self.subscriptions_component.reset_remote();
+        self.inbox = None;
+        self.sent_messages = None;
+        self.pending_read.clear();
+        self.open_conversation = None;
+    }
+
+    /// Show the history of a user in the feed.
+    pub fn open_profile(&mut self, profile: Profile, feed: PostFeed) {
+        self.reset_feed(feed);
+        self.source = FeedSource::User(profile.name.clone());
+        self.profile = Some(profile);
+    }
+
+    /// The sort of the shown listing, `None` if it isn't one.
+    pub fn sort(&self) -> Option<Sort> {
+        self.source.sort_key()?;
+
+        Some(self.sort_of(&self.source))
+    }
+
+    /// How the shown feed is sorted, for display. `None` if it can't be sorted.
+    pub fn sort_name(&self) -> Option<String> {
+        match self.source.search_sort() {
+            Some(sort) => Some(sort.to_string()),
+            None => self.sort().map(|sort| sort.to_string()),
+        }
+    }
+
+    /// How a listing was sorted last time.
+    fn sort_of(&self, source: &FeedSource) -> Sort {
+        source
+            .sort_key()
+            .and_then(|key| self.sorts.get(&key).copied())
+            .unwrap_or_default()
+    }
+
+    /// Load the frontpage or a subreddit, sorted like it was last time.
+    pub fn open_feed(&mut self, reddit: &Reddit, source: FeedSource) {
+        if let Some(feed) = source.feed(reddit, self.sort_of(&source)) {
+            if let FeedSource::Subreddit(name) = &source {
+                self.remember_subreddit(name);
+            }
+
+            self.reset_feed(feed);
+            self.source = source;
+        }
+    }
+
+    /// Open a subreddit once its first posts are fetched, so a missing or private one is reported.
+    pub fn check_subreddit(&mut self, reddit: &Reddit, name: String) {
+        let source = FeedSource::Subreddit(name.clone());
+
+        if let Some(feed) = source.feed(reddit, self.sort_of(&source)) {
+            self.subreddit_check = Some(SubredditCheck {
+                name: name.clone(),
+                result: None,
+            });
+            self.pending_subreddit = Some((name, feed));
+        }
+    }
+
+    /// Show the subreddit being checked, or why it can't be.
+    pub fn subreddit_opened(&mut self, name: &str, result: Result<(Vec<Post>, PostFeed), String>) {
+        // A subreddit the user stopped waiting for is not shown.
+        let waiting = matches!(
+            &self.subreddit_check,
+            Some(check) if check.name == name && check.result.is_none()
+        );
+        if !waiting {
+            return;
+        }
+
+        let result = result.map(|(posts, feed)| {
+            self.remember_subreddit(name);
+            self.reset_feed(feed);
+            self.source = FeedSource::Subreddit(name.to_owned());
+            self.extend_posts(posts);
+        });
+
+        self.subreddit_check = Some(SubredditCheck {
+            name: name.to_owned(),
+            result: Some(result),
+        });
+    }
+
+    fn remember_subreddit(&mut self, name: &str) {
+        self.recent_subreddits
+            .retain(|recent| !recent.eq_ignore_ascii_case(name));
+        self.recent_subreddits.insert(0, name.to_owned());
+        self.recent_subreddits.truncate(MAX_RECENT_SUBREDDITS);
+    }
+
+    /// Have reddit complete a subreddit name, unless it already did or is doing so.
+    pub fn request_subreddit_autocomplete(&mut self, query: &str) {
+        let key = query.to_lowercase();
+
+        if !self.autocompleted.contains_key(&key) && self.autocompleting.insert(key) {
+            self.pending_autocomplete.push(query.to_owned());
+        }
+    }
+
+    /// Queries that failed to complete are left without suggestions from reddit, until asked again.
+    pub fn subreddits_autocompleted(&mut self, query: &str, names: Result<Vec<String>, String>) {
+        let key = query.to_lowercase();
+        self.autocompleting.remove(&key);
+
+        if let Ok(names) = names {
+            self.autocompleted.insert(key, names);
+        }
+    }
+
+    /// Subreddits to suggest for a partly typed name, best match first. Recent subreddits
+    /// when nothing is typed, otherwise fuzzy matches among recent and subscribed subreddits
+    /// and the ones reddit completed the name, or the start of it, to.
+    pub fn subreddit_suggestions(&self, name: &str, max: usize) -> Vec<String> {
+        if name.is_empty() {
+            return self.recent_subreddits.iter().take(max).cloned().collect();
+        }
+
+        let needle = name.to_lowercase();
+        let autocompleted = self
+            .autocompleted
+            .iter()
+            .filter(|(query, _)| needle.starts_with(query.as_str()))
+            .flat_map(|(_, names)| names.iter().map(String::as_str));
+
+        let mut matches = self
+            .recent_subreddits
+            .iter()
+            .map(String::as_str)
+            .chain(self.subscriptions_component.known())
+            .chain(autocompleted)
+            .filter_map(|candidate| Some((fuzzy_score(name, candidate)?, candidate)))
+            .collect::<Vec<_>>();
+        // Stable, so recent subreddits come first among equally good matches.
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+        let mut suggestions: Vec<String> = vec![];
+        for (_, candidate) in matches {
+            if suggestions.len() == max {
+                break;
+            }
+            if !suggestions
+                .iter()
+                .any(|suggestion| suggestion.eq_ignore_ascii_case(candidate))
+            {
+                suggestions.push(candidate.to_owned());
+            }
+        }
+
+        suggestions
+    }
+
+    /// Names of the open tabs.
+    pub fn tab_titles(&self) -> Vec<String> {
+        self.tabs
+            .iter()
+            .enumerate()
+            .map(|(idx, tab)| {
+                if idx == self.active_tab {
+                    self.source.to_string()
+                } else {
+                    tab.source.to_string()
+                }
+            })
+            .collect()
+    }
+
+    /// Tabs restored from an older version, or a broken save, may be missing.
+    pub fn ensure_tabs(&mut self) {
+        if self.active_tab >= self.tabs.len() {
+            self.tabs = vec![Tab::new(self.source.clone())];
+            self.active_tab = 0;
+        }
+    }
+
+    /// Show another tab. Returns whether its feed has to be loaded.
+    pub fn switch_tab(&mut self, idx: usize) -> bool {
+        // Content still being fetched is dropped along with the fetcher's messages.
+        let fetching = self
+            .content_cache
+            .iter()
+            .filter(|(_, content)| content.is_none())
+            .map(|(post_id, _)| *post_id)
+            .collect::<Vec<_>>();
+        for post_id in fetching {
+            self.content_cache.pop(&post_id);
+        }
+
+        // The tab at `active_tab` holds nothing, so swapping parks the shown feed in it.
+        self.swap_tab(self.active_tab);
+        self.active_tab = idx;
+        self.swap_tab(idx);
+
+        self.comments = None;
+
+        self.feed.is_none() && self.posts.is_empty()
+    }
+
+    fn swap_tab(&mut self, idx: usize) {
+        let tab = &mut self.tabs[idx];
+
+        std::mem::swap(&mut self.source, &mut tab.source);
+        std::mem::swap(&mut self.feed, &mut tab.feed);
+        std::mem::swap(&mut self.posts, &mut tab.posts);
+        std::mem::swap(&mut self.profile, &mut tab.profile);
+        std::mem::swap(&mut self.active_filters, &mut tab.active_filters);
+        std::mem::swap(&mut self.content_cache, &mut tab.content_cache);
+        std::mem::swap(&mut self.feed_component.highlighted, &mut tab.highlighted);
+        std::mem::swap(&mut self.feed_component.viewed, &mut tab.viewed);
+        std::mem::swap(&mut self.history, &mut tab.history);
+    }
+
+    pub fn remove_tab(&mut self, idx: usize) {
+        self.tabs.remove(idx);
+
+        if idx < self.active_tab {
+            self.active_tab -= 1;
+        }
+    }
+
+    /// Forget the posts of every tab, and where they have been, as they were fetched for another account.
+    pub fn unload_tabs(&mut self) {
+        for (idx, tab) in self.tabs.iter_mut().enumerate() {
+            if idx != self.active_tab {
+                tab.unload();
+            }
+        }
+
+        self.posts.clear();
+        self.history = History::default();
+    }
+
+    /// Sort the shown feed, and remember the sort for it.
+    pub fn set_sort(&mut self, reddit: &Reddit, sort: Sort) {
+        match self.source.sort_key() {
+            Some(key) => {
+                self.sorts.insert(key, sort);
+                self.open_feed(reddit, self.source.clone());
+            }
+            None => self.toasts.push("Profiles can't be sorted"),
+        }
+    }
+
+    /// Sort the shown search. The sort is part of the search, so it is opened again.
+    pub fn set_search_sort(&mut self, reddit: &Reddit, sort: Sort<SearchSort>) {
+        if let FeedSource::Search {
+            query, subreddit, ..
+        } = &self.source
+        {
+            let source = FeedSource::Search {
+                query: query.clone(),
+                subreddit: subreddit.clone(),
+                sort,
+            };
+            self.open_feed(reddit, source);
+        }
+    }
+
+    pub fn set_profile_about(&mut self, name: &str, about: Result<About, String>) {
+        match (self.profile.as_mut(), about) {
+            (Some(profile), Ok(about)) if profile.name == name => profile.about = Some(about),
+            (_, Err(err)) => self
+                .toasts
+                .push(format!("Could not load /u/{}: {}", name, err)),
+            _ => {}
+        }
+    }
+
+    pub fn set_profile_comments(
+        &mut self,
+        name: &str,
+        feed: CommentFeed,
+        comments: Result<Vec<UserComment>, String>,
+    ) {
+        if let Some(profile) = self.profile.as_mut().filter(|profile| profile.name == name) {
+            match comments {
+                Ok(comments) => profile.extend_comments(comments, feed),
+                Err(err) => {
+                    // The feed is kept, so loading more comments can be tried again.
+                    profile.comments.get_or_insert_with(Vec::new);
+                    profile.comment_feed = Some(feed);
+                    self.toasts
+                        .push(format!("Could not load comments of /u/{}: {}", name, err));
+                }
+            }
+        }
+    }
+
+    /// The feed to fetch more comments of the shown profile from, unless they are being fetched.
+    pub fn take_comment_feed(&mut self) -> Option<(String, CommentFeed)> {
+        let profile = self.profile.as_mut()?;
+        let feed = profile.comment_feed.take()?;
+
+        Some((profile.name.clone(), feed))
+    }
+
+    pub fn set_comments(&mut self, key: CommentsKey, comments: Vec<Reply>) {
+        if let Some(tree) = self.comments.as_mut().filter(|tree| tree.key == key) {
+            tree.set_comments(comments);
+        }
+    }
+
+    pub fn comments_failed(&mut self, key: CommentsKey, err: String) {
+        if let Some(tree) = self.comments.as_mut().filter(|tree| tree.key == key) {
+            tree.error = Some(err);
+        }
+    }
+
     pub fn set_feed(&mut self, mut feed: PostFeed) {
         assert!(self.feed.is_none());
         feed.limit = 15;
@@ -133,14 +1213,76 @@ impl State {
     }
 
     pub fn extend_posts(&mut self, posts: Vec<Post>) {
-        let mut idx = self.posts.len();
+        // A submitted post is shown on top of its subreddit, and can come again in the listing.
+        let shown: HashSet<String> = self
+            .posts
+            .iter()
+            .map(|post| post.inner.name.clone())
+            .collect();
+        let posts = posts.into_iter().filter(|post| !shown.contains(&post.name));
 
-        for post in posts {
+        for (idx, post) in (self.posts.len()..).zip(posts) {
             self.posts.push((idx, post).into());
-            idx += 1;
         }
     }
 
+    /// Highlight the next post after the highlighted one that links to an image.
+    /// Returns whether a post was found.
+    pub fn highlight_next_image(&mut self) -> bool {
+        let highlighted = self.feed_component.highlighted;
+        let next = self
+            .get_working_posts()
+            .enumerate()
+            .skip(highlighted + 1)
+            .find(|(_, post)| post.is_image())
+            .map(|(i, _)| i);
+
+        match next {
+            Some(i) => {
+                self.feed_component.highlighted = i;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The currently viewed post.
+    pub fn viewed_post(&self) -> Option<&ViewablePost> {
+        self.get_working_posts().nth(self.feed_component.viewed)
+    }
+
+    pub fn get_post(&self, post_id: PostId) -> Option<&ViewablePost> {
+        self.posts.iter().find(|post| post.post_id == post_id)
+    }
+
+    /// The poll of a post, as reddit sent it.
+    fn fetched_poll(&self, post_id: PostId) -> Option<Poll> {
+        self.get_post(post_id).and_then(|post| {
+            let inner = post.content_post();
+            inner
+                .poll_data
+                .as_ref()
+                .map(|data| Poll::new(post_id, inner.selftext.as_ref(), data))
+        })
+    }
+
+    /// Show a poll vote before reddit has answered.
+    pub fn record_poll_vote(&mut self, post_id: PostId, option_id: &str) {
+        if let Some(mut poll) = self.fetched_poll(post_id) {
+            poll.vote(option_id);
+            self.content_cache.put(post_id, Some(Box::new(poll)));
+        }
+    }
+
+    /// Roll back a poll vote reddit rejected.
+    pub fn poll_vote_failed(&mut self, post_id: PostId, err: String) {
+        if let Some(poll) = self.fetched_poll(post_id) {
+            self.content_cache.put(post_id, Some(Box::new(poll)));
+        }
+
+        self.toasts.push(format!("Poll vote failed: {}", err));
+    }
+
     pub fn set_content(&mut self, post_id: &PostId, content: Box<dyn Render>) {
         if let Some(empty_content) = self.content_cache.get_mut(post_id) {
             assert!(empty_content.is_none());
@@ -149,12 +1291,12 @@ impl State {
     }
 
     fn filter_posts<'a>(
-        posts: &'a Vec<ViewablePost>,
+        posts: &'a [ViewablePost],
         filters: &HashMap<u32, fn(&&ViewablePost) -> bool>,
     ) -> Box<dyn Iterator<Item = &'a ViewablePost> + 'a> {
         let mut iter: Box<dyn Iterator<Item = &ViewablePost>> = Box::new(posts.iter());
         for filter in filters.values() {
-            iter = Box::new(iter.filter(filter.clone()));
+            iter = Box::new(iter.filter(*filter));
         }
 
         iter
@@ -162,45 +1304,84 @@ impl State {
 }
 
 impl State {
-    pub fn render_summary_component(&self, ctx: &CtxRef, me: Option<&Me>) {
+    pub fn render_summary_component(&self, ctx: &CtxRef, me: Option<&Me>) -> Vec<Intent> {
         self.summary_component.render(
             ctx,
             &self.options,
-            self.posts.get(self.feed_component.viewed),
+            FeedHeader {
+                source: &self.source,
+                sort: self.sort_name(),
+                profile: self.profile.as_ref(),
+            },
+            self.viewed_post(),
             me,
-        );
+            self.unread_count(),
+        )
     }
 
-    pub fn render_feed_component(&mut self, ctx: &CtxRef, has_moved: bool) {
-        self.feed_component.render(
+    pub fn render_subscriptions_component(&mut self, ctx: &CtxRef, logged_in: bool) -> Vec<Intent> {
+        self.subscriptions_component
+            .render(ctx, &self.options, logged_in)
+    }
+
+    pub fn render_feed_component(&mut self, ctx: &CtxRef, has_moved: bool) -> Vec<Intent> {
+        let (request, intents) = self.feed_component.render(
             Self::filter_posts(&self.posts, &self.active_filters),
+            self.profile.as_ref(),
+            (&self.tab_titles(), self.active_tab),
             ctx,
             &self.options,
             has_moved,
         );
+
+        if request.is_some() {
+            self.pending_tab = request;
+        }
+
+        intents
     }
 
-    pub fn render_main_content(&mut self, ctx: &CtxRef) {
-        let post = Self::filter_posts(&self.posts, &self.active_filters)
-            .skip(self.feed_component.viewed)
-            .next();
+    pub fn render_comments_component(&mut self, ctx: &CtxRef) -> Vec<Intent> {
+        self.comments_component
+            .render(ctx, &self.options, self.comments.as_mut())
+    }
+
+    pub fn render_main_content(&mut self, ctx: &CtxRef) -> Vec<Intent> {
+        let post =
+            Self::filter_posts(&self.posts, &self.active_filters).nth(self.feed_component.viewed);
 
         let content = Box::new(String::from("Loading..")) as Box<dyn Render>;
 
         let mut content = &content;
 
         if let Some(post) = post {
-            if let Some(maybe_cached) = self.content_cache.get(&post.post_id) {
-                if let Some(cached_content) = maybe_cached {
-                    content = cached_content;
-                }
+            if let Some(Some(cached_content)) = self.content_cache.get(&post.post_id) {
+                content = cached_content;
             }
         }
 
-        self.main_component.render(ctx, &self.options, content);
+        self.main_component
+            .render(ctx, &self.options, content.as_ref())
     }
 }
 
-fn empty_map() -> LruCache<PostId, Option<Box<dyn Render>>> {
-    LruCache::new(250)
+pub fn empty_map() -> LruCache<PostId, Option<Box<dyn Render>>> {
+    LruCache::new(CONTENT_CACHE_SIZE)
+}
+
+/// How many subreddits opened lately are remembered.
+const MAX_RECENT_SUBREDDITS: usize = 20;
+
+/// Id of the filter removing hidden posts.
+const HIDDEN_FILTER: u32 = 1;
+
+pub fn default_filters() -> HashMap<u32, fn(&&ViewablePost) -> bool> {
+    let mut filters: HashMap<u32, fn(&&ViewablePost) -> bool> = HashMap::new();
+    filters.insert(HIDDEN_FILTER, |post| !post.hidden);
+
+    filters
+}
+
+fn posts_focus() -> Focus {
+    Focus::Posts
 }
@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use lru::LruCache;
+use serde::{Deserialize, Serialize};
+use snew::things::{Post, PostFeed};
+
+use crate::{
+    components::{PostId, ViewablePost},
+    feed::FeedSource,
+    history::History,
+    profile::Profile,
+    state::{default_filters, empty_map},
+    Render,
+};
+
+/// How many posts each tab keeps the content of.
+pub const CONTENT_CACHE_SIZE: usize = 100;
+
+/// A feed open in a tab that is not shown. The shown tab lives in the fields of [`crate::state::State`].
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Tab {
+    pub source: FeedSource,
+    #[serde(skip)]
+    pub feed: Option<PostFeed>,
+    #[serde(skip)]
+    pub posts: Vec<ViewablePost>,
+    #[serde(skip)]
+    pub profile: Option<Profile>,
+    #[serde(skip)]
+    #[serde(default = "default_filters")]
+    pub active_filters: HashMap<u32, fn(&&ViewablePost) -> bool>,
+    #[serde(skip)]
+    #[serde(default = "empty_map")]
+    pub content_cache: LruCache<PostId, Option<Box<dyn Render>>>,
+    #[serde(skip)]
+    pub highlighted: PostId,
+    #[serde(skip)]
+    pub viewed: PostId,
+    #[serde(skip)]
+    pub history: History,
+}
+
+impl Tab {
+    pub fn new(source: FeedSource) -> Self {
+        Self {
+            source,
+            feed: None,
+            posts: vec![],
+            profile: None,
+            active_filters: default_filters(),
+            content_cache: empty_map(),
+            highlighted: 0,
+            viewed: 0,
+            history: History::default(),
+        }
+    }
+
+    /// Forget the posts, so they are loaded again when the tab is shown.
+    pub fn unload(&mut self) {
+        *self = Self::new(self.source.clone());
+    }
+}
+
+/// What the tab strip, or the app, wants done with the tabs.
+#[derive(Debug)]
+pub enum TabRequest {
+    New(FeedSource),
+    /// A new tab on the subreddit of the post, with the post on top.
+    NewWithPost(Box<Post>),
+    Switch(usize),
+    Close(usize),
+}
@@ -0,0 +1,84 @@
+use std::time::{Duration, Instant};
+
+use eframe::egui::{self, CtxRef};
+
+use crate::Intent;
+
+/// Short lived notifications, shown in the bottom right corner.
+#[derive(Debug, Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+#[derive(Debug)]
+struct Toast {
+    text: String,
+    shown_at: Instant,
+    /// Button label, and what it does.
+    action: Option<(String, Intent)>,
+}
+
+impl Toasts {
+    const DURATION: Duration = Duration::from_secs(4);
+
+    pub fn push(&mut self, text: impl ToString) {
+        self.toasts.push(Toast {
+            text: text.to_string(),
+            shown_at: Instant::now(),
+            action: None,
+        });
+    }
+
+    /// A toast with a button, e.g. to undo what it notifies about.
+    pub fn push_with_action(&mut self, text: impl ToString, label: impl ToString, intent: Intent) {
+        self.toasts.push(Toast {
+            text: text.to_string(),
+            shown_at: Instant::now(),
+            action: Some((label.to_string(), intent)),
+        });
+    }
+
+    /// Called every frame. Returns the intents of clicked buttons.
+    pub fn render(&mut self, ctx: &CtxRef) -> Vec<Intent> {
+        let mut intents = vec![];
+
+        self.toasts
+            .retain(|toast| toast.shown_at.elapsed() < Self::DURATION);
+
+        if self.toasts.is_empty() {
+            return intents;
+        }
+
+        let mut clicked = None;
+
+        egui::Area::new("toasts")
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10f32, -10f32))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(&toast.text);
+
+                            if let Some((label, intent)) = &toast.action {
+                                if ui.button(label).clicked() {
+                                    intents.push(intent.clone());
+                                    clicked = Some(i);
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+
+        // A toast has done its job once its button is clicked.
+        if let Some(i) = clicked {
+            self.toasts.remove(i);
+        }
+
+        // Keep repainting, so toasts disappear on time.
+        ctx.request_repaint();
+
+        intents
+    }
+}
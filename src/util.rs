@@ -0,0 +1,120 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Human readable duration, e.g. "2 days" or "5 hours".
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+
+    let (amount, unit) = match secs {
+        s if s >= 60 * 60 * 24 => (s / (60 * 60 * 24), "day"),
+        s if s >= 60 * 60 => (s / (60 * 60), "hour"),
+        s if s >= 60 => (s / 60, "minute"),
+        s => (s, "second"),
+    };
+
+    format!("{} {}{}", amount, unit, if amount == 1 { "" } else { "s" })
+}
+
+/// How long ago a unix timestamp was, e.g. "5 hours ago".
+pub fn format_age(created_utc: f64) -> String {
+    let created = UNIX_EPOCH + Duration::from_secs_f64(created_utc.max(0f64));
+    let age = SystemTime::now()
+        .duration_since(created)
+        .unwrap_or_default();
+
+    format!("{} ago", format_duration(age))
+}
+
+/// Short human readable count, e.g. "950", "12.3k" or "1.2M".
+pub fn format_count(count: u64) -> String {
+    match count {
+        c if c >= 1_000_000 => format!("{:.1}M", c as f64 / 1_000_000f64),
+        c if c >= 1_000 => format!("{:.1}k", c as f64 / 1_000f64),
+        c => c.to_string(),
+    }
+}
+
+/// How well `needle` matches `haystack`, ignoring case, if all its characters appear in order.
+/// Higher is better: prefixes and runs of consecutive characters score more, longer haystacks less.
+pub fn fuzzy_score(needle: &str, haystack: &str) -> Option<i32> {
+    let haystack = haystack.to_lowercase().chars().collect::<Vec<char>>();
+
+    let mut score = 0;
+    let mut from = 0;
+    let mut previous: Option<usize> = None;
+
+    for c in needle.to_lowercase().chars() {
+        let found = from + haystack[from..].iter().position(|h| *h == c)?;
+
+        score += match previous {
+            Some(previous) if found == previous + 1 => 3,
+            None if found == 0 => 5,
+            _ => 1,
+        };
+
+        previous = Some(found);
+        from = found + 1;
+    }
+
+    Some(score * 10 - haystack.len() as i32)
+}
+
+/// The percent-decoded value of a parameter in the query of `url`.
+pub fn query_param(url: &str, name: &str) -> Option<String> {
+    let (_, query) = url.split_once('?')?;
+    let (_, value) = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == name)?;
+
+    let mut bytes = value.bytes();
+    let mut decoded = Vec::with_capacity(value.len());
+
+    while let Some(byte) = bytes.next() {
+        decoded.push(match byte {
+            b'%' => {
+                let hex = bytes.by_ref().take(2).map(char::from).collect::<String>();
+                u8::from_str_radix(&hex, 16).ok()?
+            }
+            b'+' => b' ',
+            byte => byte,
+        });
+    }
+
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{fuzzy_score, query_param};
+
+    #[test]
+    fn matches_characters_in_order() {
+        assert!(fuzzy_score("rst", "rust").is_some());
+        assert!(fuzzy_score("PrGrmHmr", "ProgrammerHumor").is_some());
+        assert!(fuzzy_score("tsur", "rust").is_none());
+        assert!(fuzzy_score("rusty", "rust").is_none());
+    }
+
+    #[test]
+    fn prefers_prefixes_and_short_names() {
+        let score = |haystack| fuzzy_score("rust", haystack).unwrap();
+
+        assert!(score("rust") > score("rust_gamedev"));
+        assert!(score("rust_gamedev") > score("playrust"));
+        assert!(score("playrust") > score("r_u_s_t"));
+    }
+
+    #[test]
+    fn decodes_query_params() {
+        let url = "https://www.reddit.com/api/v1/authorize?client_id=abc&state=x1y2\
+                   &redirect_uri=http%3A%2F%2F127.0.0.1%3A8080&scope=read+identity";
+
+        assert_eq!(
+            query_param(url, "redirect_uri").as_deref(),
+            Some("http://127.0.0.1:8080")
+        );
+        assert_eq!(query_param(url, "state").as_deref(), Some("x1y2"));
+        assert_eq!(query_param(url, "scope").as_deref(), Some("read identity"));
+        assert_eq!(query_param(url, "duration"), None);
+    }
+}